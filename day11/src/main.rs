@@ -1,3 +1,7 @@
+use std::convert::TryFrom;
+use geom::{DenseGrid, Grid, Offset, Pos};
+use parser::*;
+
 
 // --- model
 
@@ -8,90 +12,59 @@ enum Cell {
     Occupied
 }
 
-impl From<char> for Cell {
-    fn from(c: char) -> Self {
+impl TryFrom<char> for Cell {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            'L' => Cell::Empty,
-            '#' => Cell::Occupied,
-            _ => Cell::Floor
+            'L' => Ok(Cell::Empty),
+            '#' => Ok(Cell::Occupied),
+            '.' => Ok(Cell::Floor),
+            _ => Err("`L`, `#` or `.`")
         }
     }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 struct Layout {
-    grid: Vec<Vec<Cell>>,
-    width: usize,
-    height: usize
+    grid: DenseGrid<Cell>
 }
 
 impl From<&str> for Layout {
     fn from(s: &str) -> Self {
-        let grid: Vec<Vec<Cell>> = s.lines().map(|line| line.trim().chars().map(Cell::from).collect()).collect();
+        let grid: Vec<Vec<Cell>> = grid()
+            .parse_complete(s)
+            .unwrap_or_else(|e| panic!("{}", e));
         Layout {
-            width: grid[0].len(),
-            height: grid.len(),
-            grid
-        }
-    }
-}
-
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
-struct Pos {
-    x: usize,
-    y: usize
-}
-
-impl Pos {
-    fn neighbours(&self) -> impl Iterator<Item = Pos> + '_ {
-        let xmin = if self.x == 0 { 0 } else { self.x - 1 };
-        let ymin = if self.y == 0 { 0 } else { self.y - 1 };
-        let xmax = self.x + 1;
-        let ymax = self.y + 1;
-        (ymin..=ymax).flat_map(
-            move |y| (xmin..=xmax).map(
-                move |x| Pos { x, y }
-            )
-        ).filter(move |p| p != self)
-    }
-}
-
-#[derive(Debug, Eq, PartialEq)]
-struct Offset {
-    x: i64,
-    y: i64
-}
-
-impl std::ops::Add<&Offset> for Pos {
-    type Output = Pos;
-    fn add(self, off: &Offset) -> Pos {
-        Pos { 
-            x: (self.x as i64 + off.x) as usize,
-            y: (self.y as i64 + off.y) as usize
+            grid: DenseGrid::from_rows(grid)
         }
     }
 }
 
 impl Layout {
     fn valid_pos(&self, p: &Pos) -> bool {
-        p.x < self.width && p.y < self.height
+        self.grid.get(p).is_some()
     }
 
+    // Outside the layout is all floor
     fn current(&self, p: &Pos) -> Cell {
-        self.grid[p.y][p.x]
+        self.grid.get(p).copied().unwrap_or(Cell::Floor)
+    }
+
+    fn neighbours(&self, p: &Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.grid.neighbours(p)
     }
 
     fn occupied_neighbours(&self, p: &Pos) -> usize {
-        p.neighbours()
-            .filter(|p| 
-                self.valid_pos(p) && self.current(p) == Cell::Occupied
-            ).count()
+        self.neighbours(p)
+            .filter(|p| self.current(p) == Cell::Occupied)
+            .count()
     }
 
     fn find_seat_in_direction(&self, p: &Pos, dir: &Offset) -> Cell {
         let mut pos = *p;
         loop {
-            pos = pos + dir;
+            pos += *dir;
             if !self.valid_pos(&pos) {
                 return Cell::Floor;
             } else {
@@ -104,40 +77,18 @@ impl Layout {
     }
 
     fn visible_occupied_seats(&self, p: &Pos) -> usize {
-        let directions = vec![
-            Offset { x: -1, y: -1 },
-            Offset { x:  0, y: -1 },
-            Offset { x:  1, y: -1 },
-            Offset { x: -1, y:  0 },
-            Offset { x:  1, y:  0 },
-            Offset { x: -1, y:  1 },
-            Offset { x:  0, y:  1 },
-            Offset { x:  1, y:  1 }
-        ];
-
-        directions.iter()
+        Offset::ALL_DIRECTIONS.iter()
             .filter(|d| self.find_seat_in_direction(p, d) == Cell::Occupied)
             .count()
     }
 
-    fn iter(&self) -> impl Iterator<Item = Cell> + '_ {
-        self.grid.iter().flat_map(|row| row.iter().cloned())
-    }
-
     fn count_occupied_seats(&self) -> usize {
-        self.iter().filter(|c| *c == Cell::Occupied).count()
+        self.grid.cells().filter(|c| **c == Cell::Occupied).count()
     }
 
     fn next_generation<F>(&self, f: F) -> Layout where F: Fn(&Pos) -> Cell {
-        let grid = self.grid.iter().enumerate().map(
-             |(y,row)| row.iter().enumerate().map(
-                |(x,_)| f(&Pos { x, y })
-             ).collect()
-        ).collect();
         Layout {
-            width: self.width,
-            height: self.height,
-            grid
+            grid: DenseGrid::from_fn(self.grid.width(), self.grid.height(), |p| f(&p))
         }
     }
 
@@ -260,6 +211,12 @@ mod tests {
         assert_eq!(layout.current(&Pos { x: 1, y: 0 }), Cell::Floor);
     }
 
+    #[test]
+    #[should_panic(expected = "line 2, column 2: expected `L`, `#` or `.`")]
+    fn test_corrupt_cell_is_located() {
+        let _ = Layout::from("L.\nLx");
+    }
+
     #[test]
     fn test_bounds() {
         let layout = Layout::from(test_grid());
@@ -271,13 +228,14 @@ mod tests {
 
     #[test]
     fn test_neighbours() {
-        let ns: Vec<Pos> = Pos { x: 0, y: 0 }.neighbours().collect();
+        let layout = Layout::from(test_grid());
+        let ns: Vec<Pos> = layout.neighbours(&Pos { x: 0, y: 0 }).collect();
         assert!(ns.contains(&Pos { x: 1, y: 0 }));
         assert!(ns.contains(&Pos { x: 0, y: 1 }));
         assert!(ns.contains(&Pos { x: 1, y: 1 }));
         assert_eq!(ns.len(), 3);
 
-        let ns: Vec<Pos> = Pos { x: 5, y: 0 }.neighbours().collect();
+        let ns: Vec<Pos> = layout.neighbours(&Pos { x: 5, y: 0 }).collect();
         assert!(ns.contains(&Pos { x: 4, y: 0 }));
         assert!(ns.contains(&Pos { x: 6, y: 0 }));
         assert!(ns.contains(&Pos { x: 4, y: 1 }));
@@ -285,7 +243,7 @@ mod tests {
         assert!(ns.contains(&Pos { x: 6, y: 1 }));
         assert_eq!(ns.len(), 5);
 
-        let ns: Vec<Pos> = Pos { x: 0, y: 8 }.neighbours().collect();
+        let ns: Vec<Pos> = layout.neighbours(&Pos { x: 0, y: 8 }).collect();
         assert!(ns.contains(&Pos { x: 0, y: 7 }));
         assert!(ns.contains(&Pos { x: 0, y: 9 }));
         assert!(ns.contains(&Pos { x: 1, y: 7 }));
@@ -293,7 +251,7 @@ mod tests {
         assert!(ns.contains(&Pos { x: 1, y: 9 }));
         assert_eq!(ns.len(), 5);
 
-        let ns: Vec<Pos> = Pos { x: 6, y: 3 }.neighbours().collect();
+        let ns: Vec<Pos> = layout.neighbours(&Pos { x: 6, y: 3 }).collect();
         assert!(ns.contains(&Pos { x: 5, y: 2 }));
         assert!(ns.contains(&Pos { x: 6, y: 2 }));
         assert!(ns.contains(&Pos { x: 7, y: 2 }));
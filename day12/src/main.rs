@@ -1,19 +1,29 @@
 use std::ops::{Add, Sub};
+use geom::{Offset, Pos, Transform};
 use parser::*;
 
 // --- model
 
-type Distance = i64;
-type Rotation = i64;
+// Distances and angles are never negative, so they're unsigned and widened
+// to i64 for the arithmetic
+type Distance = u32;
+type Rotation = u32;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Parse, Unparse)]
 enum Instruction {
+    #[parse("N{}")]
     North(Distance),
+    #[parse("S{}")]
     South(Distance),
+    #[parse("E{}")]
     East(Distance),
+    #[parse("W{}")]
     West(Distance),
+    #[parse("L{}")]
     Left(Rotation),
+    #[parse("R{}")]
     Right(Rotation),
+    #[parse("F{}")]
     Forward(Distance)
 }
 
@@ -41,7 +51,7 @@ impl Add<&Rotation> for Direction {
     type Output = Direction;
 
     fn add(self, r: &Rotation) -> Direction {
-        Direction::from(((self as i64) + r) % 360)
+        Direction::from(((self as i64) + i64::from(*r)) % 360)
     }
 }
 
@@ -49,40 +59,17 @@ impl Sub<&Rotation> for Direction {
     type Output = Direction;
 
     fn sub(self, r: &Rotation) -> Direction {
-        Direction::from(((self as i64) + 360 - r) % 360)
+        Direction::from(((self as i64) + 360 - i64::from(*r)) % 360)
     }
 }
 
 impl Direction {
-    fn to_instruction(&self, distance: Distance) -> Instruction {
+    fn offset(&self) -> Offset {
         match self {
-            Direction::North => Instruction::North(distance),
-            Direction::South => Instruction::South(distance),
-            Direction::East => Instruction::East(distance),
-            Direction::West => Instruction::West(distance)            
-        }
-    }
-}
-
-#[derive(Debug,PartialEq,Eq)]
-struct Pos {
-    x: i64,
-    y: i64
-}
-
-impl Pos {
-    fn rotate_around(&self, origin: &Pos, rotation: Rotation) -> Pos {
-        let x = self.x - origin.x;
-        let y = self.y - origin.y;
-        let (new_x, new_y) = match rotation {
-            90 => (-y, x),
-            180 => (-x, -y),
-            270 => (y, -x),
-            _ => panic!("invalid rotation")
-        };
-        Pos {
-            x: origin.x + new_x,
-            y: origin.y + new_y
+            Direction::North => Offset::NORTH,
+            Direction::South => Offset::SOUTH,
+            Direction::East => Offset::EAST,
+            Direction::West => Offset::WEST
         }
     }
 }
@@ -95,7 +82,7 @@ struct Ship {
 impl Ship {
     fn new() -> Self {
         Ship {
-            pos: Pos { x: 0, y: 0 },
+            pos: Pos::default(),
             direction: Direction::East
         }
     }
@@ -103,75 +90,76 @@ impl Ship {
     fn go(&mut self, inst: &Instruction) {
         use Instruction::*;
         match inst {
-            North(n) => self.pos.y += n,
-            South(n) => self.pos.y -= n,
-            East(n) => self.pos.x += n,
-            West(n) => self.pos.x -= n,
+            North(n) => self.pos += Offset::NORTH * i64::from(*n),
+            South(n) => self.pos += Offset::SOUTH * i64::from(*n),
+            East(n) => self.pos += Offset::EAST * i64::from(*n),
+            West(n) => self.pos += Offset::WEST * i64::from(*n),
             Left(n) => self.direction = self.direction + n,
             Right(n) => self.direction = self.direction - n,
-            Forward(n) => self.go(&self.direction.to_instruction(*n))
+            Forward(n) => self.pos += self.direction.offset() * i64::from(*n)
         }
     }
 
-    fn manhattan_distance_from_start(&self) -> Distance {
-        self.pos.x.abs() + self.pos.y.abs()
+    fn manhattan_distance_from_start(&self) -> i64 {
+        self.pos.manhattan(Pos::default())
     }
 }
 
+// The waypoint moves with the ship, so it's kept relative to the ship
 struct WaypointShip {
     ship: Pos,
-    waypoint: Pos
+    waypoint: Offset
 }
 
 impl WaypointShip {
     fn new() -> Self {
         WaypointShip {
-            ship: Pos { x: 0, y : 0 },
-            waypoint: Pos { x: 10, y: 1 }
+            ship: Pos::default(),
+            waypoint: Offset::EAST * 10 + Offset::NORTH
         }
     }
 
+    // Anticlockwise, as the ship's directions turn
+    fn turn(&mut self, rotation: i64) {
+        let transform = Transform::rotation(rotation)
+            .unwrap_or_else(|| panic!("invalid rotation {}", rotation));
+        self.waypoint = transform.apply(self.waypoint);
+    }
+
     fn go(&mut self, inst: &Instruction) {
         use Instruction::*;
         match inst {
-            North(n) => self.waypoint.y += n,
-            South(n) => self.waypoint.y -= n,
-            East(n) => self.waypoint.x += n,
-            West(n) => self.waypoint.x -= n,
-            Left(n) => self.waypoint = self.waypoint.rotate_around(&self.ship, *n),
-            Right(n) => self.waypoint = self.waypoint.rotate_around(&self.ship, 360-(*n)),
-            Forward(n) => {
-                let x = (self.waypoint.x - self.ship.x) * n;
-                let y = (self.waypoint.y - self.ship.y) * n;
-                self.ship.x += x;
-                self.ship.y += y;
-                self.waypoint.x += x;
-                self.waypoint.y += y;
-            }
+            North(n) => self.waypoint += Offset::NORTH * i64::from(*n),
+            South(n) => self.waypoint += Offset::SOUTH * i64::from(*n),
+            East(n) => self.waypoint += Offset::EAST * i64::from(*n),
+            West(n) => self.waypoint += Offset::WEST * i64::from(*n),
+            Left(n) => self.turn(i64::from(*n)),
+            Right(n) => self.turn(-i64::from(*n)),
+            Forward(n) => self.ship += self.waypoint * i64::from(*n)
         }
     }
 
-    fn manhattan_distance_from_start(&self) -> Distance {
-        self.ship.x.abs() + self.ship.y.abs()
+    fn manhattan_distance_from_start(&self) -> i64 {
+        self.ship.manhattan(Pos::default())
     }
 }
 
 // --- parser
 
-fn parse_input(input: &str) -> ParseResult<Vec<Instruction>> {
-    let north = right(match_literal("N"), integer).map(Instruction::North);
-    let south = right(match_literal("S"), integer).map(Instruction::South);
-    let east = right(match_literal("E"), integer).map(Instruction::East);
-    let west = right(match_literal("W"), integer).map(Instruction::West);
-    let tright = right(match_literal("R"), integer).map(Instruction::Right);
-    let tleft = right(match_literal("L"), integer).map(Instruction::Left);
-    let forward = right(match_literal("F"), integer).map(Instruction::Forward);
-    let instruction = north.or(south).or(east).or(west).or(tright).or(tleft).or(forward);
-    let parser = one_or_more(whitespace_wrap(instruction));
+fn parse_input(input: &str) -> ParseResult<Recovered<Spanned<Instruction>>> {
+    let parser = recover_lines(Instruction::parser().spanned());
 
     parser.parse(input)
 }
 
+// Turns other than right angles, which the ship can't make
+fn is_invalid_turn(inst: &Instruction) -> bool {
+    match inst {
+        Instruction::Left(r) | Instruction::Right(r) => ![90, 180, 270].contains(r),
+        _ => false
+    }
+}
+
 // --- problems
 
 fn part1(instructions: &Vec<Instruction>) -> i64 {
@@ -188,7 +176,18 @@ fn part2(instructions: &Vec<Instruction>) -> i64 {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let instructions = parse_input(&input).unwrap().1;
+    let parsed = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
+    for error in parsed.errors.iter() {
+        eprintln!("{}", error.locate(&input));
+    }
+    let invalid: Vec<_> = parsed.records.iter().filter(|inst| is_invalid_turn(inst)).collect();
+    for turn in invalid.iter() {
+        let (line, col) = turn.locate(input.as_str());
+        eprintln!("line {}, column {}: can't turn `{}`", line, col, turn.unparsed());
+    }
+    assert!(invalid.is_empty(), "{} turns aren't right angles", invalid.len());
+    let instructions = parsed.records.into_iter().map(|inst| inst.value).collect();
     println!("part1 {}", part1(&instructions));
     println!("part2 {}", part2(&instructions));
 }
@@ -196,14 +195,80 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    fn values(records: Vec<Spanned<Instruction>>) -> Vec<Instruction> {
+        records.into_iter().map(|inst| inst.value).collect()
+    }
 
     #[test]
     fn test_parser() {
         use Instruction::*;
-        let instructions = parse_input("F10\nN3\nF7\nR90\nF11");
+        let instructions = parse_input("F10\nN3\nF7\nR90\nF11").map(|(rest, parsed)| (rest, values(parsed.records)));
         assert_eq!(instructions, Ok(("", vec![Forward(10), North(3), Forward(7), Right(90), Forward(11)])));
     }
 
+    #[test]
+    fn test_parser_error() {
+        use Instruction::*;
+        let input = "\nX10\nF7";
+        let parsed = parse_input(input).unwrap().1;
+        assert_eq!(values(parsed.records), vec![Forward(7)]);
+        assert_eq!(
+            parsed.errors[0].locate(input).to_string(),
+            "parse error at line 2, column 1: expected `N`, `S`, `E`, `W`, `L`, `R` or `F`, found \"X10\""
+        );
+    }
+
+    #[test]
+    fn test_parser_rejects_negative_values() {
+        use Instruction::*;
+        let parsed = parse_input("N-5\nF-7\nL-90\nF7").unwrap().1;
+        assert_eq!(values(parsed.records), vec![Forward(7)]);
+        assert_eq!(parsed.errors.len(), 3);
+    }
+
+    #[test]
+    fn test_print_round_trips_input() {
+        let input = "F10\nN3\nF7\nR90\nF11\n";
+        let instructions = parse_input(input).unwrap().1.records;
+        assert_eq!(print_lines(unparser()).printed(&instructions[..]), input);
+    }
+
+    #[test]
+    fn test_invalid_turns() {
+        let input = "F10\nL90\nR45\nN3\nL360\n";
+        let parsed = parse_input(input).unwrap().1;
+        let invalid: Vec<usize> = parsed.records.iter()
+            .filter(|inst| is_invalid_turn(inst))
+            .map(|turn| turn.locate(input).0)
+            .collect();
+        assert_eq!(invalid, vec![3, 5]);
+    }
+
+    fn instruction() -> impl Strategy<Value = Instruction> {
+        use Instruction::*;
+        prop_oneof![
+            (0..=Distance::MAX).prop_map(North),
+            (0..=Distance::MAX).prop_map(South),
+            (0..=Distance::MAX).prop_map(East),
+            (0..=Distance::MAX).prop_map(West),
+            (0..=Rotation::MAX).prop_map(Left),
+            (0..=Rotation::MAX).prop_map(Right),
+            (0..=Distance::MAX).prop_map(Forward)
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_print_round_trip(instructions in prop::collection::vec(instruction(), 0..20)) {
+            let text = print_lines(unparser()).printed(&instructions[..]);
+            let parsed = parse_input(&text).unwrap().1;
+            prop_assert!(parsed.errors.is_empty());
+            prop_assert_eq!(values(parsed.records), instructions);
+        }
+    }
+
     #[test]
     fn test_part1() {
         use Instruction::*;
@@ -218,25 +283,31 @@ mod tests {
         assert_eq!(part2(&instructions), 286);
     }
 
+    fn turned(rotation: i64) -> Offset {
+        let mut ship = WaypointShip::new();
+        ship.turn(rotation);
+        ship.waypoint
+    }
+
     #[test]
-    fn test_rotate_around_90() {
-        let origin = Pos { x: 0, y: 0 };
-        let pos = Pos { x: 10, y: 1 };
-        assert_eq!(pos.rotate_around(&origin, 90), Pos { x: -1, y: 10 });
+    fn test_turn_left_90() {
+        assert_eq!(turned(90), Offset::NORTH * 10 + Offset::WEST);
     }
 
     #[test]
-    fn test_rotate_around_180() {
-        let origin = Pos { x: 0, y: 0 };
-        let pos = Pos { x: 10, y: 1 };
-        assert_eq!(pos.rotate_around(&origin, 180), Pos { x: -10, y: -1 });
+    fn test_turn_left_180() {
+        assert_eq!(turned(180), Offset::WEST * 10 + Offset::SOUTH);
     }
 
     #[test]
-    fn test_rotate_around_270() {
-        let origin = Pos { x: 0, y: 0 };
-        let pos = Pos { x: 10, y: 1 };
-        assert_eq!(pos.rotate_around(&origin, 270), Pos { x: 1, y: -10 });
+    fn test_turn_left_270() {
+        assert_eq!(turned(270), Offset::SOUTH * 10 + Offset::EAST);
+        assert_eq!(turned(-90), turned(270));
     }
 
-}
\ No newline at end of file
+    #[test]
+    #[should_panic(expected = "invalid rotation 45")]
+    fn test_turn_non_right_angle() {
+        turned(45);
+    }
+}
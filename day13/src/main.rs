@@ -23,7 +23,7 @@ impl From<&str> for Input {
             |estimate, bus_ids| Input { estimate, bus_ids }
         );
 
-        input.parse(s).unwrap().1
+        input.parse_complete(s).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
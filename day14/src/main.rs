@@ -6,9 +6,11 @@ use parser::*;
 type Address = u64;
 type Word = u64;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Parse, Unparse)]
 enum Instruction {
+    #[parse(with = "mask", unparse = "unparse_mask")]
     Mask { zeros: Word, ones: Word },
+    #[parse("mem[{address}] = {value}")]
     Write { address: Address, value: Word }
 }
 
@@ -81,7 +83,7 @@ impl Machine {
 
 // -- parser
 
-fn parse_input(input: &str) -> ParseResult<Program> {
+fn mask(input: &str) -> ParseResult<'_, Instruction> {
     #[derive(Copy,Clone)]
     enum MaskBit {
         Zero,
@@ -106,18 +108,21 @@ fn parse_input(input: &str) -> ParseResult<Program> {
             Instruction::Mask { zeros, ones }
         });
 
-    let write = pair(
-        right(match_literal("mem["), integer),
-        right(match_literal("] = "), integer),
-        |address, value| Instruction::Write {
-            address: address as Address,
-            value: value as Word
+    mask.parse(input)
+}
+
+fn unparse_mask(instruction: &Instruction, out: &mut String) {
+    if let Instruction::Mask { zeros, ones } = instruction {
+        out.push_str("mask = ");
+        for bit_index in (0..36).rev() {
+            let bit: Word = 1 << bit_index;
+            out.push(if zeros & bit != 0 { '0' } else if ones & bit != 0 { '1' } else { 'X' });
         }
-    );
+    }
+}
 
-    let program = zero_or_more(whitespace_wrap(
-            either(mask, write)
-    ));
+fn parse_input(input: &str) -> ParseResult<Program> {
+    let program = zero_or_more(whitespace_wrap(Instruction::parser()));
 
     program.parse(input)
 }
@@ -138,7 +143,8 @@ fn part2(program: &Program) -> Word {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let program = parse_input(&input).unwrap().1;
+    let program = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
     println!("part 1 {:?}", part1(&program));
     println!("part 2 {:?}", part2(&program));
 }
@@ -146,6 +152,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     fn sample_program() -> &'static str {
         "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
@@ -165,6 +172,29 @@ mod tests {
         ])));
     }
 
+    #[test]
+    fn test_print_round_trips_input() {
+        let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\nmem[8] = 11\nmem[7] = 101\nmem[8] = 0\n";
+        let program = parse_input(input).unwrap().1;
+        assert_eq!(print_lines(unparser()).printed(&program[..]), input);
+    }
+
+    fn instruction() -> impl Strategy<Value = Instruction> {
+        let bits = 0..(1 as Word) << 36;
+        prop_oneof![
+            (bits.clone(), bits).prop_map(|(zeros, ones)| Instruction::Mask { zeros: zeros & !ones, ones }),
+            any::<(Address, Word)>().prop_map(|(address, value)| Instruction::Write { address, value })
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_print_round_trip(program in prop::collection::vec(instruction(), 0..20)) {
+            let text = print_lines(unparser()).printed(&program[..]);
+            prop_assert_eq!(parse_input(&text), Ok(("", program)));
+        }
+    }
+
     #[test]
     fn test_part1() {
         let program = parse_input(sample_program()).unwrap().1;
@@ -161,19 +161,15 @@ fn parse_input(input: &str) -> ParseResult<TicketData> {
     let field_range = tuple2(
         left(field_name, match_literal(":")),
         whitespace_wrap(ranges)
-    );
+    ).label("field range");
 
-    let csv = integer.sep_by(match_literal(","));
+    let csv = integer.sep_by(match_literal(",")).label("ticket");
 
-    let your_ticket = right(
-        whitespace_wrap(match_literal("your ticket:")),
-        csv.clone()
-    );
+    let your_ticket = section(match_literal("your ticket:"), line(csv.clone()))
+        .context("your ticket");
 
-    let nearby_tickets = right(
-        whitespace_wrap(match_literal("nearby tickets:")),
-        one_or_more(whitespace_wrap(csv))
-    );
+    let nearby_tickets = section(match_literal("nearby tickets:"), one_or_more(line(csv)))
+        .context("nearby tickets");
 
     let ticket_data = tuple3(one_or_more(field_range), your_ticket, nearby_tickets)
         .map(|(field_ranges, your_ticket, nearby_tickets)| TicketData {
@@ -206,7 +202,14 @@ fn part2(ticket_data: &TicketData) -> i64 {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let ticket_data = parse_input(&input).unwrap().1;
+    #[cfg(feature = "trace")]
+    parser::record_trace_to_file("./parse-trace.txt").unwrap();
+
+    let ticket_data = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    #[cfg(feature = "trace")]
+    parser::finish_trace().unwrap();
 
     println!("part 1 {:?}", part1(&ticket_data));
     println!("part 2 {:?}", part2(&ticket_data));
@@ -1,8 +1,7 @@
-use std::cmp::{max, min};
-use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
-use std::hash::Hash;
-use std::ops::{AddAssign, RangeInclusive};
+use geom::{BoundsN, Grid, PosN, SparseGrid};
+use parser::*;
 
 // --- model
 
@@ -12,136 +11,72 @@ enum Cube {
     Active
 }
 
-impl From<char> for Cube {
-    fn from(c: char) -> Self {
+impl TryFrom<char> for Cube {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            '#' => Cube::Active,
-            _ => Cube::Inactive
+            '#' => Ok(Cube::Active),
+            '.' => Ok(Cube::Inactive),
+            _ => Err("`#` or `.`")
         }
     }
 }
 
-trait Position: Eq + Hash {
-    fn neighbours(&self) -> Box<dyn Iterator<Item = Self> + '_>;
-}
-
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-struct Pos3(i64, i64, i64);
-
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-struct Pos4(i64, i64, i64, i64);
-
-impl Position for Pos3 {
-    fn neighbours(&self) -> Box<dyn Iterator<Item = Self> + '_> {
-        let it = (-1..=1).flat_map(
-            move |z| (-1..=1).flat_map(
-                move |y| (-1..=1).map(
-                    move |x| Pos3(self.0+x, self.1+y, self.2+z)
-                )
-            )
-        ).filter(move |p| p != self);
-
-        Box::new(it)
-    }
-}
-
-impl Position for Pos4 {
-    fn neighbours(&self) -> Box<dyn Iterator<Item = Self> + '_> {
-        let it = (-1..=1).flat_map(
-            move |w| (-1..=1).flat_map(
-                move |z| (-1..=1).flat_map(
-                    move |y| (-1..=1).map(
-                        move |x| Pos4(self.0+x, self.1+y, self.2+z, self.3+w)
-                    )
-                )
-            )
-        ).filter(move |p| p != self);
-
-        Box::new(it)
-    }
-}
-
-struct Bounds3 {
-    x: RangeInclusive<i64>,
-    y: RangeInclusive<i64>,
-    z: RangeInclusive<i64>
-}
-
-struct Bounds4 {
-    x: RangeInclusive<i64>,
-    y: RangeInclusive<i64>,
-    z: RangeInclusive<i64>,
-    w: RangeInclusive<i64>
+// A pocket dimension of any number of dimensions from three up. The initial
+// layers fill x, y and z, and every other coordinate starts at the origin's.
+#[derive(Clone)]
+struct PocketDimension<const N: usize> {
+    grid: SparseGrid<Cube, PosN<N>>
 }
 
-impl Default for Bounds3 {
-    fn default() -> Self {
-        Bounds3 {
-            x: 0..=0,
-            y: 0..=0,
-            z: 0..=0
+impl<const N: usize> PocketDimension<N> {
+    fn new(origin: PosN<N>, s: &str) -> Self {
+        assert!(N >= 3, "a pocket dimension needs at least three dimensions");
+        let mut grid = SparseGrid::new(Cube::Inactive);
+
+        for (z, layer) in layers(s).into_iter().enumerate() {
+            for (y, row) in layer.into_iter().enumerate() {
+                for (x, cube) in row.into_iter().enumerate() {
+                    let mut pos = origin;
+                    pos[0] += x as i64;
+                    pos[1] += y as i64;
+                    pos[2] += z as i64;
+                    grid.insert(pos, cube);
+                }
+            }
         }
-    }
-}
 
-impl Default for Bounds4 {
-    fn default() -> Self {
-        Bounds4 {
-            x: 0..=0,
-            y: 0..=0,
-            z: 0..=0,
-            w: 0..=0
-        }
+        PocketDimension { grid }
     }
-}
 
-impl AddAssign<Pos3> for Bounds3 {
-    fn add_assign(&mut self, pos: Pos3) {
-        self.x = min(*self.x.start(), pos.0) ..= max(*self.x.end(), pos.0);
-        self.y = min(*self.y.start(), pos.1) ..= max(*self.y.end(), pos.1);
-        self.z = min(*self.z.start(), pos.2) ..= max(*self.z.end(), pos.2);
+    fn at(&self, p: &PosN<N>) -> &Cube {
+        &self.grid[*p]
     }
-}
 
-impl AddAssign<Pos4> for Bounds4 {
-    fn add_assign(&mut self, pos: Pos4) {
-        self.x = min(*self.x.start(), pos.0) ..= max(*self.x.end(), pos.0);
-        self.y = min(*self.y.start(), pos.1) ..= max(*self.y.end(), pos.1);
-        self.z = min(*self.z.start(), pos.2) ..= max(*self.z.end(), pos.2);
-        self.w = min(*self.w.start(), pos.3) ..= max(*self.w.end(), pos.3);
+    fn bounds(&self) -> BoundsN<N> {
+        self.grid.bounds()
     }
-}
-
-trait Dimension<Pos: Position + Copy> where Self: Sized {
-    fn grid(&self) -> &HashMap<Pos, Cube>;
-
-    fn iter(&self) -> Box<dyn Iterator<Item = Pos> + '_>;
 
-    fn at(&self, p: &Pos) -> &Cube;
-
-    fn next_generation(&self) -> Self;
+    // Every position which could be active in the next generation
+    fn iter(&self) -> impl Iterator<Item = PosN<N>> {
+        self.bounds().grow(1).lattice()
+    }
 
-    fn occupied_neighbours(&self, p: &Pos) -> usize {
-        p.neighbours()
+    fn occupied_neighbours(&self, p: &PosN<N>) -> usize {
+        p.moore_neighbours()
             .filter(|p| 
                 self.at(p) == &Cube::Active
             ).count()
     }
 
-    fn bounds<Bounds: Default + AddAssign<Pos>>(&self) -> Bounds {
-        let mut bounds = Bounds::default();
-        for pos in self.grid().keys() {
-            bounds += *pos;
-        }
-        bounds
-    }
-
     fn active_cubes(&self) -> usize {
-        self.grid().values().filter(|c| *c == &Cube::Active).count()
+        self.grid.iter().filter(|(_, c)| *c == &Cube::Active).count()
     }
 
-    fn next_generation_grid(&self) -> HashMap<Pos, Cube> {
-        self.iter().map(|pos| {
+    fn next_generation(&self) -> Self {
+        let mut grid = SparseGrid::new(Cube::Inactive);
+        for pos in self.iter() {
             let occupied = self.occupied_neighbours(&pos);
             let new_state = match self.at(&pos) {
                 Cube::Active => 
@@ -158,151 +93,19 @@ trait Dimension<Pos: Position + Copy> where Self: Sized {
                         Cube::Inactive
                     }
             };
-            (pos, new_state)
-        }).collect()
-    }
-}
-
-#[derive(Clone)]
-struct PocketDimension<Pos: Position> {
-    grid: HashMap<Pos, Cube>
-}
-
-
-impl PartialEq for PocketDimension<Pos3> {
-    fn eq(&self, other: &Self) -> bool {
-        let mut bounds: Bounds3 = self.bounds();
-        for pos in other.iter() { 
-            bounds += pos;
-        }
-        for z in bounds.z {
-            for y in (&bounds.y).clone() {
-                for x in (&bounds.x).clone() {
-                    let pos = Pos3(x, y, z);
-                    if self.at(&pos) != other.at(&pos) {
-                        return false;
-                    }
-                }
-            }
-        }
-        true
-    }
-}
-
-impl PartialEq for PocketDimension<Pos4> {
-    fn eq(&self, other: &Self) -> bool {
-        let mut bounds: Bounds4 = self.bounds();
-        for pos in other.iter() { 
-            bounds += pos;
-        }
-        for w in bounds.w {
-            for z in bounds.z.clone() {
-                for y in (&bounds.y).clone() {
-                    for x in (&bounds.x).clone() {
-                        let pos = Pos4(x, y, z, w);
-                        if self.at(&pos) != other.at(&pos) {
-                            return false;
-                        }
-                    }
-                }
-            }
-        }
-        true
-    }
-}
-
-impl PocketDimension<Pos3> {
-    fn new3(origin: &Pos3, s: &str) -> Self {
-        let mut grid = HashMap::new();
-
-        for (z, zs) in s.split("\n\n").enumerate() {
-            for (y, ys) in zs.lines().enumerate() {
-                for (x, xs) in ys.trim().chars().enumerate() {
-                    grid.insert(Pos3(origin.0 + x as i64, origin.1 + y as i64, origin.2 + z as i64), Cube::from(xs));
-                }
-            }
-        }
-
-        PocketDimension { grid }
-    }
-}
-
-impl Dimension<Pos3> for PocketDimension<Pos3> {
-    fn grid(&self) -> &HashMap<Pos3, Cube> {
-        &self.grid
-    }
-
-    fn at(&self, p: &Pos3) -> &Cube {
-        self.grid.get(p).unwrap_or(&Cube::Inactive)
-    }
-
-    fn iter(&self) -> Box<dyn Iterator<Item = Pos3> + '_> {
-        let bounds: Bounds3 = self.bounds();
-        let (xmin, xmax) = (*bounds.x.start() - 1, *bounds.x.end() + 1);
-        let (ymin, ymax) = (*bounds.y.start() - 1, *bounds.y.end() + 1);
-        let (zmin, zmax) = (*bounds.z.start() - 1, *bounds.z.end() + 1);
-
-        let it = (zmin..=zmax).flat_map(move |z|
-            (ymin..=ymax).flat_map(move |y|
-                (xmin..=xmax).map(move |x| Pos3(x, y, z) )
-            )
-        );
-
-        Box::new(it)
-    }
-
-    fn next_generation(&self) -> Self {
-        PocketDimension { grid: self.next_generation_grid() }
-    }
-}
-
-impl PocketDimension<Pos4> {
-    fn new4(origin: &Pos4, s: &str) -> Self {
-        let mut grid = HashMap::new();
-
-        for (z, zs) in s.split("\n\n").enumerate() {
-            for (y, ys) in zs.lines().enumerate() {
-                for (x, xs) in ys.trim().chars().enumerate() {
-                    grid.insert(Pos4(origin.0 + x as i64, origin.1 + y as i64, origin.2 + z as i64, 0), Cube::from(xs));
-                }
-            }
+            grid.insert(pos, new_state);
         }
 
         PocketDimension { grid }
     }
 }
 
-impl Dimension<Pos4> for PocketDimension<Pos4> {
-    fn grid(&self) -> &HashMap<Pos4, Cube> {
-        &self.grid
-    }
-
-    fn at(&self, p: &Pos4) -> &Cube {
-        self.grid.get(p).unwrap_or(&Cube::Inactive)
-    }
-
-    fn iter(&self) -> Box<dyn Iterator<Item = Pos4> + '_> {
-        let bounds: Bounds4 = self.bounds();
-        let (xmin, xmax) = (*bounds.x.start() - 1, *bounds.x.end() + 1);
-        let (ymin, ymax) = (*bounds.y.start() - 1, *bounds.y.end() + 1);
-        let (zmin, zmax) = (*bounds.z.start() - 1, *bounds.z.end() + 1);
-        let (wmin, wmax) = (*bounds.w.start() - 1, *bounds.w.end() + 1);
-
-        let it = (wmin..=wmax).flat_map(move |w|
-            (zmin..=zmax).flat_map(move |z|
-                (ymin..=ymax).flat_map(move |y|
-                    (xmin..=xmax).map(move |x| Pos4(x, y, z, w) )
-                )
-            )
-        );
-
-        Box::new(it)
-    }
-
-    fn next_generation(&self) -> Self {
-        PocketDimension { grid: self.next_generation_grid() }
+impl<const N: usize> PartialEq for PocketDimension<N> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut bounds = self.bounds();
+        bounds.extend(other.grid.iter().map(|(pos, _)| *pos));
+        bounds.lattice().all(|pos| self.at(&pos) == other.at(&pos))
     }
-
 }
 
 impl fmt::Debug for Cube {
@@ -314,27 +117,37 @@ impl fmt::Debug for Cube {
     }
 }
 
-impl fmt::Debug for PocketDimension<Pos3> {
+// One x-y slice at a time, headed by its coordinates on the other axes
+impl<const N: usize> fmt::Debug for PocketDimension<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let bounds: Bounds3 = self.bounds();
-        write!(f, "zs={:?} ys={:?} xs={:?}\n", bounds.z, bounds.y, bounds.x)?;
-        for z in bounds.z {
-            write!(f, "z={:?}\n", z)?;
-            for y in (&bounds.y).clone() {
-                for x in (&bounds.x).clone() {
-                    write!(f, "{:?}", self.at(&Pos3(x,y,z)))?;
-                }
-                write!(f, " {}\n", y)?;
+        let bounds = self.bounds();
+        writeln!(f, "from {:?} to {:?}", bounds.min, bounds.max)?;
+        for pos in bounds.lattice() {
+            if pos[0] == bounds.min[0] && pos[1] == bounds.min[1] {
+                writeln!(f, "{:?}", &pos.0[2..])?;
+            }
+            write!(f, "{:?}", self.at(&pos))?;
+            if pos[0] == bounds.max[0] {
+                writeln!(f, " {}", pos[1])?;
             }
         }
         Ok(())
     }
 }
 
+// --- parser
+
+// One grid of cubes per z layer, with blank lines between layers
+fn layers(input: &str) -> Vec<Vec<Vec<Cube>>> {
+    paragraphs(grid())
+        .parse_complete(input)
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
 // --- problems
 
 fn part1(input: &str) -> usize {  
-    let mut p = PocketDimension::new3(&Pos3(0,0,0), input);
+    let mut p = PocketDimension::<3>::new(PosN::default(), input);
     for _ in 0..6 {
         p = p.next_generation();
     }
@@ -342,7 +155,7 @@ fn part1(input: &str) -> usize {
 }
 
 fn part2(input: &str) -> usize {
-    let mut p = PocketDimension::new4(&Pos4(0,0,0,0), input);
+    let mut p = PocketDimension::<4>::new(PosN::default(), input);
     for _ in 0..6 {
         p = p.next_generation();
     }
@@ -369,36 +182,42 @@ mod tests {
 
     #[test]
     fn test_init() {
-        let pd = PocketDimension::new3(&Pos3(0,0,0), test_grid());
-        assert_eq!(pd.at(&Pos3(0,0,0)), &Cube::Inactive);
-        assert_eq!(pd.at(&Pos3(1,0,0)), &Cube::Active);
-        assert_eq!(pd.at(&Pos3(3,6,9)), &Cube::Inactive);
-        assert_eq!(pd.at(&Pos3(2,1,0)), &Cube::Active);
+        let pd = PocketDimension::new(PosN([0,0,0]), test_grid());
+        assert_eq!(pd.at(&PosN([0,0,0])), &Cube::Inactive);
+        assert_eq!(pd.at(&PosN([1,0,0])), &Cube::Active);
+        assert_eq!(pd.at(&PosN([3,6,9])), &Cube::Inactive);
+        assert_eq!(pd.at(&PosN([2,1,0])), &Cube::Active);
+    }
+
+    #[test]
+    #[should_panic(expected = "line 2, column 2: expected `#` or `.`")]
+    fn test_corrupt_cube_is_located() {
+        layers("#.\n.x");
     }
 
     #[test]
     fn test_neighbours_3d() {
-        assert_eq!(Pos3(0,0,0).neighbours().count(), 26);
+        assert_eq!(PosN([0,0,0]).moore_neighbours().count(), 26);
     }
 
     #[test]
     fn test_neighbours_4d() {
-        assert_eq!(Pos4(0,0,0,0).neighbours().count(), 80);
+        assert_eq!(PosN([0,0,0,0]).moore_neighbours().count(), 80);
     }
 
     #[test]
     fn test_occupied_neighbours() {
-        let pd = PocketDimension::new3(&Pos3(0,0,0), test_grid());
-        assert_eq!(pd.occupied_neighbours(&Pos3(0,0,0)), 1);        
-        assert_eq!(pd.occupied_neighbours(&Pos3(1,2,0)), 3);        
+        let pd = PocketDimension::new(PosN([0,0,0]), test_grid());
+        assert_eq!(pd.occupied_neighbours(&PosN([0,0,0])), 1);        
+        assert_eq!(pd.occupied_neighbours(&PosN([1,2,0])), 3);        
     }
 
     #[test]
     fn test_generations() {
-        let pd = PocketDimension::new3(&Pos3(0,0,0), test_grid());
+        let pd = PocketDimension::new(PosN([0,0,0]), test_grid());
 
         let gen1 = pd.next_generation();
-        assert_eq!(gen1, PocketDimension::new3(&Pos3(0,1,-1),
+        assert_eq!(gen1, PocketDimension::new(PosN([0,1,-1]),
             "#..
              ..#
              .#.
@@ -413,7 +232,7 @@ mod tests {
         ));
 
         let gen2 = gen1.next_generation();
-        assert_eq!(gen2, PocketDimension::new3(&Pos3(-1,0,-2),
+        assert_eq!(gen2, PocketDimension::new(PosN([-1,0,-2]),
             ".....
              .....
              ..#..
@@ -448,11 +267,20 @@ mod tests {
 
     #[test]
     fn test_six_generations_v1() {
-        let mut p = PocketDimension::new3(&Pos3(0,0,0), test_grid());
+        let mut p = PocketDimension::new(PosN([0,0,0]), test_grid());
         for _ in 0..6 {
              p = p.next_generation();
         }
         assert_eq!(p.active_cubes(), 112);
     }
 
+    #[test]
+    fn test_six_generations_v2() {
+        let mut p = PocketDimension::<4>::new(PosN::default(), test_grid());
+        for _ in 0..6 {
+             p = p.next_generation();
+        }
+        assert_eq!(p.active_cubes(), 848);
+    }
+
 }
\ No newline at end of file
@@ -1,177 +1,65 @@
-use std::collections::HashMap;
-use std::iter::{empty, once};
 use parser::*;
 
 // --- model
 
-type RuleID = usize;
+// Rules 8 and 11 are replaced by recursive versions in part 2
+const MODIFICATION: &str = "
+    8: 42 | 42 8
+    11: 42 31 | 42 11 31
+";
 
-#[derive(Debug, Clone, PartialEq)]
-enum Rule {
-    MatchChar(char),
-    Sequence(Vec<RuleID>),
-    Alternative(Vec<RuleID>, Vec<RuleID>)
+fn is_valid(rules: &Grammar, message: &str) -> bool {
+    matches!(rules.parser("0").unwrap().parse(message), Ok(("", _)))
 }
 
-#[derive(Debug, PartialEq)]
-struct Rules {
-    rules: HashMap<RuleID, Rule>
-}
-
-type MatchResult<'a> = Box<dyn Iterator<Item = &'a str> + 'a>;
-
-impl Rules {
-    fn get(&self, id: &RuleID) -> &Rule {
-        self.rules.get(id).unwrap()
-    }
-
-    fn match_seq_tail_recursive<'a>(&'a self, seq: &'a [RuleID], input: &'a str) -> MatchResult<'a> {
-        let mut remaining: Vec<&str> = self.match_seq_non_recursive(seq, input).collect();
-        let mut results: MatchResult<'a> = Box::new(empty());
-        while !remaining.is_empty() {
-            let next_remaining = remaining.iter().flat_map(|r|
-                self.match_seq_non_recursive(seq, r)
-            ).collect();
-
-            results = Box::new(results.chain(remaining.into_iter()));
-            remaining = next_remaining;
-        }
-        results
-    }
-
-    fn match_seq_non_recursive<'a>(&'a self, seq: &'a [RuleID], input: &'a str) -> MatchResult<'a> {
-        seq.iter().fold(
-            Box::new(once(input)),
-            |remainings, rule| {
-                Box::new(remainings.flat_map(move |remaining|
-                    self.match_rule(rule, remaining)
-                ))
-            }
-        )
-    }
-
-    fn match_seq<'a>(&'a self, id: &RuleID, seq: &'a [RuleID], input: &'a str) -> MatchResult<'a> {
-        if seq.last() == Some(id) {
-            self.match_seq_tail_recursive(&seq[0..seq.len()-1], input)
-        } else {
-            self.match_seq_non_recursive(seq, input)
-        }
-    }
-
-    fn match_rule<'a>(&'a self, id: &RuleID, input: &'a str) -> MatchResult<'a> {
-        match self.get(id) {
-            Rule::MatchChar(c) => {
-                if input.chars().next() == Some(*c) {
-                    Box::new(once(&input[c.len_utf8()..]))
-                } else {
-                    Box::new(empty())
-                }
-            }
-
-            Rule::Sequence(rs) => {
-                self.match_seq(id, rs, input)
-            }
-
-            Rule::Alternative(xs, ys) => {
-                let mut r = self.match_seq(id, xs, input).peekable();
-                if r.peek().is_some() {
-                    Box::new(r)
-                } else {
-                    self.match_seq(id, ys, input)
-                }
-            }
-        }
-    }
-
-    fn match_all<'a>(&self, input: &'a str) -> Result<(), &'a str> {
-        let mut r = self.match_rule(&0, input);
-        match r.next() {
-            None => Err("no match"),
-            Some(s) if s.is_empty() => Ok(()),
-            _ => Err("extra unmatched input")
-        }
-    }
-
-    fn apply_modification(&mut self) {
-        // self.rules.insert(8, Rule::OneOrMore(42));
-        self.rules.insert(8, Rule::Alternative(vec![42, 8], vec![42]));
-        self.rules.insert(11, Rule::Alternative(vec![42, 31], vec!(42, 11, 31)));
-    }
-}
-
-// --- parser
-
-fn parse_rules(input: &str) -> ParseResult<Rules> {
-    let rule_id = integer.map(|i| i as RuleID);
-    let space = match_literal(" ");
-    
-    let match_char = any_char
-        .between(match_literal(" \""), match_literal("\""))
-        .map(Rule::MatchChar);
-
-    let raw_sequence = one_or_more(right(space, rule_id.clone())).boxed();
-    let sequence = raw_sequence.clone().map(Rule::Sequence);
+// -- parser
 
-    let alternative = pair(left(raw_sequence.clone(), match_literal(" |")), raw_sequence,
-        |a, b| Rule::Alternative(a, b)
-    );
-
-    let rule = pair(
-        left(rule_id, match_literal(":")),
-        match_char.or(alternative).or(sequence),
-        |id, def| (id, def)
-    );
-
-    let rules = one_or_more(whitespace_wrap(rule))
-        .map(|rs| Rules {
-            rules: rs.into_iter().collect()
-        });
-
-    rules.parse(input)
+fn parse_input(input: &str) -> ParseResult<'_, (Grammar, Vec<&str>)> {
+    let messages = one_or_more(line(word_ref));
+    tuple2(grammar(), messages).parse(input)
 }
 
 // -- problems 
 
-fn count_valid_messages(rules: &Rules, messages: &[&str]) -> usize {
-    messages.iter().filter_map(|m| rules.match_all(m).ok()).count()
+fn count_valid_messages(rules: &Grammar, messages: &[&str]) -> usize {
+    messages.iter().filter(|m| is_valid(rules, m)).count()
 }
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
     
-    let mut sections = input.split("\n\n");
-    let mut rules = parse_rules(sections.next().unwrap()).unwrap().1;
-    let messages: Vec<&str> = sections.next().unwrap().lines().collect();
+    #[cfg(feature = "trace")]
+    parser::record_trace_to_file("./parse-trace.txt").unwrap();
+
+    let (mut rules, messages) = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    #[cfg(feature = "trace")]
+    parser::finish_trace().unwrap();
 
     println!("part 1 {}", count_valid_messages(&rules, &messages));
 
-    rules.apply_modification();
+    rules.extend(MODIFICATION).unwrap();
     println!("part 2 {}", count_valid_messages(&rules, &messages));
 }
 
-#[cfg(test)]
-#[macro_use] extern crate maplit;
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn sample_rules() -> Rules {
-        use Rule::*;
-        Rules {
-            rules: hashmap![
-                0 => Sequence(vec![4, 1, 5]),
-                1 => Alternative(vec![2, 3], vec![3, 2]),
-                2 => Alternative(vec![4, 4], vec![5, 5]),
-                3 => Alternative(vec![4, 5], vec![5, 4]),
-                4 => MatchChar('a'),
-                5 => MatchChar('b')
-            ]
-        }
+    fn sample_rules() -> Grammar {
+        Grammar::new(
+            "0: 4 1 5
+             1: 2 3 | 3 2
+             2: 4 4 | 5 5
+             3: 4 5 | 5 4
+             4: \"a\"
+             5: \"b\""
+        ).unwrap()
     }
 
-    fn part2_sample_rules() -> Rules {
-        parse_rules(
+    fn part2_sample_rules() -> Grammar {
+        Grammar::new(
             "42: 9 14 | 10 1
              9: 14 27 | 1 26
              10: 23 14 | 28 1
@@ -203,12 +91,12 @@ mod tests {
              18: 15 15
              7: 14 5 | 1 21
              24: 14 1
-").unwrap().1
+").unwrap()
     }
 
-    fn part2_sample_rules_modified() -> Rules {
+    fn part2_sample_rules_modified() -> Grammar {
         let mut rules = part2_sample_rules();
-        rules.apply_modification();
+        rules.extend(MODIFICATION).unwrap();
         rules
     }
 
@@ -232,74 +120,61 @@ aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba".lines()
 
     #[test]
     fn test_parser() {
-        let rules = parse_rules(
-            "0: 4 1 5
-             1: 2 3 | 3 2
-             2: 4 4 | 5 5
-             3: 4 5 | 5 4
-             4: \"a\"
-             5: \"b\""
-        );
-
-        assert_eq!(rules, Ok(("", sample_rules())));
+        let message = sample_rules().parser("0").unwrap();
+        assert_eq!(message.parse("ababbb"), Ok(("", "ababbb")));
+        assert_eq!(message.parse("aaaabbb"), Ok(("b", "aaaabb")));
     }
 
     #[test]
-    fn test_matcher_success() {
-        let rules = sample_rules();
-        
-        let result: Vec<&str> = rules.match_rule(&0, "ababbb").collect();
-        assert_eq!(result, vec![""]);
-
-        let result: Vec<&str> = rules.match_rule(&0, "abbbab").collect();
-        assert_eq!(result, vec![""]);
-
-        let result: Vec<&str> = rules.match_rule(&0, "aaaabbb").collect();
-        assert_eq!(result, vec![("b")]);
+    fn test_parser_failure() {
+        let message = sample_rules().parser("0").unwrap();
+        assert_eq!(message.parse("bababa").map_err(|e| e.expected), Err(vec!["`a`".to_string()]));
+        assert!(message.parse("aaabbb").is_err());
     }
 
     #[test]
-    fn test_matcher_failure() {
-        let rules = sample_rules();
-        assert_eq!(rules.match_rule(&0, "bababa").next(), None);
-        assert_eq!(rules.match_rule(&0, "aaabbb").next(), None);
+    fn test_parse_input() {
+        let (rules, messages) = parse_input("0: 1 1\r\n1: \"a\"\r\n\r\naa\r\nab\r\n\r\n").unwrap().1;
+        assert_eq!(messages, vec!["aa", "ab"]);
+        assert!(is_valid(&rules, messages[0]));
+        assert!(!is_valid(&rules, messages[1]));
     }
 
     #[test]
-    fn test_match_all() {
+    fn test_is_valid() {
         let rules = sample_rules();
-        assert_eq!(rules.match_all("abbbab"), Ok(()));
-        assert_eq!(rules.match_all("aaaabbb"), Err("extra unmatched input"));        
+        assert!(is_valid(&rules, "abbbab"));
+        assert!(!is_valid(&rules, "aaaabbb"));
     }
 
     #[test]
     fn test_part2_rules_without_modification() {
         let rules = part2_sample_rules();
         let messages = part2_input();
-        assert_eq!(messages.filter_map(|m| rules.match_all(m).ok()).count(), 3);
+        assert_eq!(messages.filter(|m| is_valid(&rules, m)).count(), 3);
     }
 
     #[test]
     fn test_part2_rules_with_modification() {
         let rules = part2_sample_rules_modified();
         let messages = part2_input();
-        assert_eq!(messages.filter_map(|m| rules.match_all(m).ok()).count(), 12);
+        assert_eq!(messages.filter(|m| is_valid(&rules, m)).count(), 12);
     }
 
     #[test]
     fn test_part2_rules_with_modification_individual_cases() {
         let rules = part2_sample_rules_modified();
-        assert_eq!(rules.match_all("bbabbbbaabaabba"), Ok(()));
-        assert_eq!(rules.match_all("babbbbaabbbbbabbbbbbaabaaabaaa"), Ok(()));
-        assert_eq!(rules.match_all("aaabbbbbbaaaabaababaabababbabaaabbababababaaa"), Ok(()));
-        assert_eq!(rules.match_all("bbbbbbbaaaabbbbaaabbabaaa"), Ok(()));
-        assert_eq!(rules.match_all("bbbababbbbaaaaaaaabbababaaababaabab"), Ok(()));
-        assert_eq!(rules.match_all("ababaaaaaabaaab"), Ok(()));
-        assert_eq!(rules.match_all("ababaaaaabbbaba"), Ok(()));
-        assert_eq!(rules.match_all("baabbaaaabbaaaababbaababb"), Ok(()));
-        assert_eq!(rules.match_all("abbbbabbbbaaaababbbbbbaaaababb"), Ok(()));
-        assert_eq!(rules.match_all("aaaaabbaabaaaaababaa"), Ok(()));
-        assert_eq!(rules.match_all("aaaabbaabbaaaaaaabbbabbbaaabbaabaaa"), Ok(()));
-        assert_eq!(rules.match_all("aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba"), Ok(()));
+        assert!(is_valid(&rules, "bbabbbbaabaabba"));
+        assert!(is_valid(&rules, "babbbbaabbbbbabbbbbbaabaaabaaa"));
+        assert!(is_valid(&rules, "aaabbbbbbaaaabaababaabababbabaaabbababababaaa"));
+        assert!(is_valid(&rules, "bbbbbbbaaaabbbbaaabbabaaa"));
+        assert!(is_valid(&rules, "bbbababbbbaaaaaaaabbababaaababaabab"));
+        assert!(is_valid(&rules, "ababaaaaaabaaab"));
+        assert!(is_valid(&rules, "ababaaaaabbbaba"));
+        assert!(is_valid(&rules, "baabbaaaabbaaaababbaababb"));
+        assert!(is_valid(&rules, "abbbbabbbbaaaababbbbbbaaaababb"));
+        assert!(is_valid(&rules, "aaaaabbaabaaaaababaa"));
+        assert!(is_valid(&rules, "aaaabbaabbaaaaaaabbbabbbaaabbaabaaa"));
+        assert!(is_valid(&rules, "aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba"));
     }
 }
\ No newline at end of file
@@ -1,8 +1,6 @@
 
-mod parser;
-
 use std::ops::Range;
-use parser::*;
+use parser::compat::*;
 
 
 // ---- model
@@ -33,8 +31,8 @@ fn password(input: &str) -> ParseResult<Password> {
 
 // --- input file
 
-fn parse_input(input: &str) -> ParseResult<Vec<Password>> {
-    let p = one_or_more(first(password, whitespace));
+fn parse_input(input: &str) -> ParseResult<(Vec<Password>, Vec<ParseError>)> {
+    let p = recover_lines(password);
     p.parse(input)
 }
 
@@ -64,7 +62,10 @@ fn part2(passwords: &Vec<Password>) -> usize {
 
 fn main() {
     let input = std::fs::read_to_string("../input.txt").unwrap();
-    let (_, passwords) = parse_input(&input).unwrap();
+    let (_, (passwords, errors)) = parse_input(&input).unwrap();
+    for (expected, rest) in errors {
+        eprintln!("expected {} at {:?}", expected, rest.lines().next().unwrap_or(""));
+    }
     println!("part1 {}", part1(&passwords));
     println!("part2 {}", part2(&passwords));
 }
@@ -75,8 +76,9 @@ mod tests {
 
     #[test]
     fn test_parse_passwords() {
-        let (rest, passwords) = parse_input("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc").unwrap();
+        let (rest, (passwords, errors)) = parse_input("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc").unwrap();
         assert_eq!(rest, "");
+        assert_eq!(errors, vec![]);
         assert_eq!(passwords,
             vec![
                 Password { position1: 1, position2: 3, character: 'a', password: String::from("abcde") },
@@ -86,6 +88,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_continues_past_bad_lines() {
+        let (rest, (passwords, errors)) = parse_input("1-3 a: abcde\n1-x b: cdefg\n2-9 c: ccccccccc\n").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(passwords,
+            vec![
+                Password { position1: 1, position2: 3, character: 'a', password: String::from("abcde") },
+                Password { position1: 2, position2: 9, character: 'c', password: String::from("ccccccccc") }
+            ]
+        );
+        assert_eq!(errors, vec![("integer", "x b: cdefg\n2-9 c: ccccccccc\n")]);
+    }
+
     #[test]
     fn test_part1_is_valid_1() {
         let p = Password { position1: 1, position2: 3, character: 'a', password: String::from("abcde") };
@@ -3,15 +3,16 @@ extern crate lazy_static;
 
 use log::{debug, info};
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
+use geom::{Bounds, DenseGrid, Grid, Offset, Pos, Transform};
 use parser::*;
 
 // --- model
 
 type TileID = usize;
 type EdgePattern = u64;
+type Orientation = Transform;
 
 trait Reversible {
     fn reversed(self) -> Self;
@@ -49,16 +50,30 @@ struct Tile {
     content: Vec<Vec<char>>
 }
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, EnumIter, Hash)]
-enum Orientation {
-    R0,
-    R90,
-    R180,
-    R270,
-    R0FlipH,
-    R0FlipV,
-    R90FlipH,
-    R90FlipV
+// Edges are read left to right or top to bottom
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right
+}
+
+impl Edge {
+    const ALL: [Edge; 4] = [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right];
+
+    // The first and last corners of the edge in reading order, as cells of a
+    // 2x2 grid, so they can be transformed like any other image
+    fn corners(self) -> (Pos, Pos) {
+        let (top_left, top_right) = (Pos { x: 0, y: 0 }, Pos { x: 1, y: 0 });
+        let (bottom_left, bottom_right) = (Pos { x: 0, y: 1 }, Pos { x: 1, y: 1 });
+        match self {
+            Edge::Top => (top_left, top_right),
+            Edge::Bottom => (bottom_left, bottom_right),
+            Edge::Left => (top_left, bottom_left),
+            Edge::Right => (top_right, bottom_right)
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
@@ -76,57 +91,51 @@ impl PartialEq for Tile {
 }
 
 impl Tile {
-    fn top_edge_in_orientation(&self, orientation: Orientation) -> EdgePattern {
-        match orientation {
-            Orientation::R0 => self.top,
-            Orientation::R90 => self.right,
-            Orientation::R180 => self.bottom.reversed(),
-            Orientation::R270  => self.left.reversed(),
-            Orientation::R0FlipH => self.top.reversed(),
-            Orientation::R0FlipV => self.bottom,
-            Orientation::R90FlipH => self.right.reversed(),
-            Orientation::R90FlipV => self.left
+    fn edge(&self, edge: Edge) -> EdgePattern {
+        match edge {
+            Edge::Top => self.top,
+            Edge::Bottom => self.bottom,
+            Edge::Left => self.left,
+            Edge::Right => self.right
         }
     }
 
-    fn bottom_edge_in_orientation(&self, orientation: Orientation) -> EdgePattern {
-        match orientation {
-            Orientation::R0 => self.bottom,
-            Orientation::R90 => self.left,
-            Orientation::R180 => self.top.reversed(),
-            Orientation::R270  => self.right.reversed(),
-            Orientation::R0FlipH => self.bottom.reversed(),
-            Orientation::R0FlipV => self.top,
-            Orientation::R90FlipH => self.left.reversed(),
-            Orientation::R90FlipV => self.right
-        }
+    // Find which of the tile's own edges ends up along `edge` by taking its
+    // corners back through the orientation, reversed if they come out in the
+    // opposite order
+    fn edge_in_orientation(&self, edge: Edge, orientation: Orientation) -> EdgePattern {
+        let undo = orientation.inverse();
+        let (first, last) = edge.corners();
+        let corners = (undo.apply_in_grid(first, 2, 2), undo.apply_in_grid(last, 2, 2));
+
+        Edge::ALL.iter().find_map(|source| {
+            let (first, last) = source.corners();
+            if corners == (first, last) {
+                Some(self.edge(*source))
+            } else if corners == (last, first) {
+                Some(self.edge(*source).reversed())
+            } else {
+                None
+            }
+        }).unwrap()
     }
 
-    fn left_edge_in_orientation(&self, orientation: Orientation) -> EdgePattern {
-        match orientation {
-            Orientation::R0 => self.left,
-            Orientation::R90 => self.top.reversed(),
-            Orientation::R180 => self.right.reversed(),
-            Orientation::R270  => self.bottom,
-            Orientation::R0FlipH => self.right,
-            Orientation::R0FlipV => self.left.reversed(),
-            Orientation::R90FlipH => self.bottom.reversed(),
-            Orientation::R90FlipV =>self.top
+    fn edges_in_orientation(&self, orientation: Orientation) -> OrientedEdges {
+        OrientedEdges {
+            top: self.edge_in_orientation(Edge::Top, orientation),
+            bottom: self.edge_in_orientation(Edge::Bottom, orientation),
+            left: self.edge_in_orientation(Edge::Left, orientation),
+            right: self.edge_in_orientation(Edge::Right, orientation)
         }
     }
+}
 
-    fn right_edge_in_orientation(&self, orientation: Orientation) -> EdgePattern {
-        match orientation {
-            Orientation::R0 => self.right,
-            Orientation::R90 => self.bottom.reversed(),
-            Orientation::R180 => self.left.reversed(),
-            Orientation::R270  => self.top,
-            Orientation::R0FlipH => self.left,
-            Orientation::R0FlipV => self.right.reversed(),
-            Orientation::R90FlipH => self.top.reversed(),
-            Orientation::R90FlipV => self.bottom
-        }
-    }
+#[derive(Debug)]
+struct OrientedEdges {
+    top: EdgePattern,
+    bottom: EdgePattern,
+    left: EdgePattern,
+    right: EdgePattern
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
@@ -145,36 +154,39 @@ struct AllowedOrientedTiles {
 
 impl AllowedOrientedTiles {
     fn new(tiles: &Vec<&Tile>) -> Self {
+        // every edge of every orientation, worked out once up front
+        let oriented: Vec<(OrientedTile, OrientedEdges)> = tiles.iter().flat_map(|tile|
+            Orientation::all().map(move |orientation|
+                (OrientedTile { tile_id: tile.id, orientation }, tile.edges_in_orientation(orientation))
+            )
+        ).collect();
+
         let mut allowed = HashMap::new();
-        for tile in tiles.iter() {
-            for orientation in Orientation::iter() {
-                let mut above = HashSet::new();
-                let mut below = HashSet::new();
-                let mut left_of = HashSet::new();
-                let mut right_of = HashSet::new();
-
-                for candidate in tiles.iter().filter(|t| t.id != tile.id) {
-                    for candidate_orientation in Orientation::iter() {
-                        if candidate.bottom_edge_in_orientation(candidate_orientation) == tile.top_edge_in_orientation(orientation) {
-                            above.insert(OrientedTile { tile_id: candidate.id, orientation: candidate_orientation });
-                        }
-                        if candidate.top_edge_in_orientation(candidate_orientation) == tile.bottom_edge_in_orientation(orientation) {
-                            below.insert(OrientedTile { tile_id: candidate.id, orientation: candidate_orientation });
-                        }
-                        if candidate.left_edge_in_orientation(candidate_orientation) == tile.right_edge_in_orientation(orientation) {
-                            right_of.insert(OrientedTile { tile_id: candidate.id, orientation: candidate_orientation });
-                        }
-                        if candidate.right_edge_in_orientation(candidate_orientation) == tile.left_edge_in_orientation(orientation) {
-                            left_of.insert(OrientedTile { tile_id: candidate.id, orientation: candidate_orientation });
-                        }
-                    }
+        for (tile, edges) in oriented.iter() {
+            let mut above = HashSet::new();
+            let mut below = HashSet::new();
+            let mut left_of = HashSet::new();
+            let mut right_of = HashSet::new();
+
+            for (candidate, candidate_edges) in oriented.iter().filter(|(t, _)| t.tile_id != tile.tile_id) {
+                if candidate_edges.bottom == edges.top {
+                    above.insert(*candidate);
+                }
+                if candidate_edges.top == edges.bottom {
+                    below.insert(*candidate);
+                }
+                if candidate_edges.left == edges.right {
+                    right_of.insert(*candidate);
+                }
+                if candidate_edges.right == edges.left {
+                    left_of.insert(*candidate);
                 }
-
-                allowed.insert((tile.id, orientation, Relationship::Above), above);
-                allowed.insert((tile.id, orientation, Relationship::Below), below);
-                allowed.insert((tile.id, orientation, Relationship::LeftOf), left_of);
-                allowed.insert((tile.id, orientation, Relationship::RightOf), right_of);
             }
+
+            allowed.insert((tile.tile_id, tile.orientation, Relationship::Above), above);
+            allowed.insert((tile.tile_id, tile.orientation, Relationship::Below), below);
+            allowed.insert((tile.tile_id, tile.orientation, Relationship::LeftOf), left_of);
+            allowed.insert((tile.tile_id, tile.orientation, Relationship::RightOf), right_of);
         }
 
         AllowedOrientedTiles {
@@ -188,45 +200,6 @@ impl AllowedOrientedTiles {
     }
 } 
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-struct Pos {
-    x: i64,
-    y: i64
-}
-
-impl Pos {
-    fn up(&self) -> Pos {
-        Pos { x: self.x, y: self.y - 1 }
-    }
-
-    fn down(&self) -> Pos {
-        Pos { x: self.x, y: self. y + 1 }
-    }
-
-    fn left(&self) -> Pos {
-        Pos { x: self.x - 1, y: self.y }
-    }
-
-    fn right(&self) -> Pos {
-        Pos { x: self.x + 1, y: self.y }
-    }
-
-    fn neighbours(&self) -> impl Iterator<Item=Pos> + '_ {
-        let mut i = 0;
-        std::iter::from_fn(move || {
-            let n = i;
-            i += 1;
-            match n {
-                0 => Some(self.up()),
-                1 => Some(self.down()),
-                2 => Some(self.left()),
-                3 => Some(self.right()),
-                _ => None
-            }
-        })
-    }
-}
-
 #[derive(Debug, Eq, PartialEq)]
 enum TilePlacement<'a> {
     None,
@@ -291,7 +264,7 @@ impl<'a> Arrangement<'a> {
         if let Some(tile) = self.available_tiles.remove(&tile_id) {
             self.fixed_tiles[pos.y as usize][pos.x as usize] = TilePlacement::Placed { orientation, tile };
             self.next_positions.remove(pos);
-            for n in pos.neighbours() {
+            for n in Offset::ORTHOGONAL.iter().map(|dir| *pos + *dir) {
                 if self.valid(&n) && self.tile_at(&n) == &TilePlacement::None {
                     self.next_positions.insert(n);
                 }
@@ -338,25 +311,25 @@ impl<'a> Arrangement<'a> {
     fn possible_orientations(&self, pos: &Pos, allowed_neighbours: &AllowedOrientedTiles) -> Result<HashSet<OrientedTile>, TileID> {
         let mut possible = OrientedTileSet::new();
 
-        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&pos.left()) {
+        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&(*pos + Offset::WEST)) {
             possible.restrict_to(allowed_neighbours.get(tile.id, *orientation, Relationship::RightOf));
         }
 
-        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&pos.up()) {
+        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&(*pos + Offset::NORTH)) {
             possible.restrict_to(allowed_neighbours.get(tile.id, *orientation, Relationship::Below));
             if possible.is_empty() {
                 return Err(tile.id);
             }
         }
 
-        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&pos.right()) {
+        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&(*pos + Offset::EAST)) {
             possible.restrict_to(allowed_neighbours.get(tile.id, *orientation, Relationship::LeftOf));
             if possible.is_empty() {
                 return Err(tile.id);
             }
         }
 
-        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&pos.down()) {
+        if let TilePlacement::Placed { tile, orientation } = self.tile_at(&(*pos + Offset::SOUTH)) {
             possible.restrict_to(allowed_neighbours.get(tile.id, *orientation, Relationship::Above));
             if possible.is_empty() {
                 return Err(tile.id);
@@ -412,7 +385,7 @@ impl<'a> Arrangement<'a> {
                             let mut tile_image = Image::new(&tile.content);
                             tile_image.orientation = orientation;
                             for x in 0..8 {
-                                row.push(*tile_image.get(Pos { x, y }));
+                                row.push(*tile_image.get(&Pos { x, y }).unwrap());
                             }
                         }
                         _ => {
@@ -446,7 +419,7 @@ fn arrange_tiles<'a>(width: i64, height: i64, tiles: &Vec<&'a Tile>) -> Option<A
     let allowed_neighbours = AllowedOrientedTiles::new(tiles);
 
     tiles.iter().filter_map(|tile|
-        Orientation::iter().filter_map(|orientation| {
+        Orientation::all().filter_map(|orientation| {
             info!("trying {} {:?} in start position", tile.id, orientation);
             let mut arrangement = Arrangement::new(width, height, tiles);
             arrangement.place(&Pos { x: 0, y: 0 }, orientation, tile.id);
@@ -458,21 +431,9 @@ fn arrange_tiles<'a>(width: i64, height: i64, tiles: &Vec<&'a Tile>) -> Option<A
     ).next()
 }
 
-impl std::ops::Add<&Pos> for Pos {
-    type Output = Pos;
-    fn add(self, other: &Pos) -> Self::Output {
-        Pos {
-            x: self.x + other.x,
-            y: self.y + other.y
-        }
-    }
-}
-
 struct Image {
-    image: Vec<Vec<char>>,
-    orientation: Orientation,
-    width: usize,
-    height: usize
+    image: DenseGrid<char>,
+    orientation: Orientation
 }
 
 impl fmt::Debug for Image {
@@ -480,7 +441,7 @@ impl fmt::Debug for Image {
         writeln!(f, "orientation {:?} size {:?}x{:?}", self.orientation, self.width(), self.height())?;
         for y in 0..self.height() {
             for x in 0..self.width() {
-                write!(f, "{}", self.get(Pos { x: x as i64, y: y as i64 }))?;
+                write!(f, "{}", self.get(&Pos { x: x as i64, y: y as i64 }).unwrap())?;
             }
             write!(f, "\n")?;
         }
@@ -491,10 +452,8 @@ impl fmt::Debug for Image {
 impl Image {
     fn new(image: &Vec<Vec<char>>) -> Self {
         Image {
-            image: image.clone(),
-            orientation: Orientation::R0,
-            height: image.len(),
-            width: image[0].len()
+            image: DenseGrid::from_rows(image.clone()),
+            orientation: Transform::IDENTITY
         }
     }
 
@@ -503,65 +462,28 @@ impl Image {
     }
 
     fn width(&self) -> usize {
-        match self.orientation {
-            Orientation::R0 | Orientation::R180 | Orientation::R0FlipH | Orientation::R0FlipV => self.width,
-            Orientation::R90 | Orientation::R270 | Orientation::R90FlipH | Orientation::R90FlipV => self.height
-        }
+        self.orientation.grid_size(self.image.width() as i64, self.image.height() as i64).0 as usize
     }
 
     fn height(&self) -> usize {
-        match self.orientation {
-            Orientation::R0 | Orientation::R180 | Orientation::R0FlipH | Orientation::R0FlipV => self.height,
-            Orientation::R90 | Orientation::R270 | Orientation::R90FlipH | Orientation::R90FlipV => self.width
-        }
-    }
-
-    fn transform(&self, pos: Pos) -> (usize, usize) {
-        let x = pos.x as usize;
-        let y = pos.y as usize;
-        let rx = self.width() - 1 - x;
-        let ry = self.height() - 1 - y;
-
-        match self.orientation {
-            Orientation::R0 => (x, y),
-            Orientation::R90 => (ry, x),
-            Orientation::R180 => (rx, ry),
-            Orientation::R270 => (y, rx),
-            Orientation::R0FlipH => (rx, y),
-            Orientation::R0FlipV => (x, ry),
-            Orientation::R90FlipH => (ry, rx),
-            Orientation::R90FlipV => (y, x)
-        }
+        self.orientation.grid_size(self.image.width() as i64, self.image.height() as i64).1 as usize
     }
 
-    fn get(&self, pos: Pos) -> &char {
-        let (x, y) = self.transform(pos);
-        &self.image[y][x]
+    // Where a position in the oriented image is in the original
+    fn transform(&self, pos: Pos) -> Pos {
+        self.orientation.inverse().apply_in_grid(pos, self.width() as i64, self.height() as i64)
     }
 
-    fn get_mut(&mut self, pos: Pos) -> &mut char {
-        let (x, y) = self.transform(pos);
-        &mut self.image[y][x]
-    }
-
-    fn iter(&self) -> impl Iterator<Item = Pos> + '_ {
-        (0..self.height()).flat_map(move |y|
-            (0..self.width()).map(move |x|
-                Pos { x: x as i64, y: y as i64 }
-            )
-        )
-    }
-
-    fn has_monster_at(&self, origin: &Pos, monster: &Image) -> bool {
-        monster.iter().all(|pos| 
-            monster.get(pos) == &' ' || self.get(pos + origin) == &'#'
+    fn has_monster_at(&self, shift: Offset, monster: &Image) -> bool {
+        monster.iter_positions().all(|pos|
+            monster.get(&pos) == Some(&' ') || self.get(&(pos + shift)) == Some(&'#')
         )
     }
 
-    fn overwrite_monster(&mut self, origin: &Pos, monster: &Image) {
-        for pos in monster.iter() {
-            if monster.get(pos) == &'#' {
-                *self.get_mut(pos + origin) = 'O';
+    fn overwrite_monster(&mut self, shift: Offset, monster: &Image) {
+        for pos in monster.iter_positions() {
+            if monster.get(&pos) == Some(&'#') {
+                *self.get_mut(&(pos + shift)).unwrap() = 'O';
             }
         }
     }
@@ -570,9 +492,9 @@ impl Image {
         let mut count = 0;
         for y in 0..(self.height() - monster.height()) {
             for x in 0..(self.width() - monster.width()) {
-                let p = Pos { x: x as i64, y: y as i64 };
-                if self.has_monster_at(&p, monster) {
-                    self.overwrite_monster(&p, monster);
+                let shift = Offset { x: x as i64, y: y as i64 };
+                if self.has_monster_at(shift, monster) {
+                    self.overwrite_monster(shift, monster);
                     count += 1;
                 }
             }
@@ -581,10 +503,37 @@ impl Image {
     }
 }
 
+// The image as it appears in its current orientation
+impl Grid for Image {
+    type Position = Pos;
+    type Cell = char;
+
+    fn get(&self, pos: &Pos) -> Option<&char> {
+        if self.bounds().contains(pos) {
+            self.image.get(&self.transform(*pos))
+        } else {
+            None
+        }
+    }
+
+    fn get_mut(&mut self, pos: &Pos) -> Option<&mut char> {
+        if self.bounds().contains(pos) {
+            let source = self.transform(*pos);
+            self.image.get_mut(&source)
+        } else {
+            None
+        }
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::with_size(self.width() as i64, self.height() as i64)
+    }
+}
+
 fn find_monsters(image: &mut Image) -> usize {
     let monster = Image::from_str("                  # \n#    ##    ##    ###\n #  #  #  #  #  #   ");
 
-    Orientation::iter().filter_map(|orientation| {
+    Orientation::all().filter_map(|orientation| {
         image.orientation = orientation;
         let count = image.find_monsters(&monster);
         if count > 0 {
@@ -594,7 +543,7 @@ fn find_monsters(image: &mut Image) -> usize {
         }
     }).next();
 
-    image.iter().filter(|pos| image.get(*pos) == &'#').count()
+    image.iter_positions().filter(|pos| image.get(pos) == Some(&'#')).count()
 }
 
 // -- parser
@@ -622,14 +571,31 @@ fn trim_edges(cells: &Vec<Vec<char>>) -> Vec<Vec<char>> {
         .collect()
 }
 
-fn parse_input(input: &str) -> ParseResult<Vec<Tile>> {
-    let tile_id = integer
-        .between(match_literal("Tile "), match_literal(":\n"))
+// A pixel of a tile, which is only ever on or off
+struct TileCell(char);
+
+impl TryFrom<char> for TileCell {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '#' | '.' => Ok(TileCell(c)),
+            _ => Err("`#` or `.`")
+        }
+    }
+}
+
+fn parse_input(input: &[u8]) -> ParseResult<Vec<Spanned<Tile>>, [u8]> {
+    let tile_id = line(integer.between(match_literal("Tile "), match_literal(":")))
         .map(|i| i as TileID);
 
-    let tile_char = any_char.pred(|c| *c == '#' || *c == '.');
-    let tile_row = whitespace_wrap(one_or_more(tile_char));
-    let tile = pair(tile_id, one_or_more(tile_row), |id, cells| 
+    let cells = grid().map(|rows: Vec<Vec<TileCell>>|
+        rows.into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.0).collect())
+            .collect::<Vec<Vec<char>>>()
+    );
+
+    let tile = pair(tile_id, cells, |id, cells|
         Tile {
             id,
             top: decode_row(&cells[0]),
@@ -640,7 +606,16 @@ fn parse_input(input: &str) -> ParseResult<Vec<Tile>> {
         }
     );
 
-    one_or_more(tile).parse(input)
+    paragraphs(tile.spanned()).parse(input)
+}
+
+// Tiles which can't be fitted alongside the first because they're a different size
+fn odd_sized_tiles(tiles: &[Spanned<Tile>]) -> Vec<&Spanned<Tile>> {
+    let size = |tile: &Tile| (tile.content.len(), tile.content.first().map_or(0, Vec::len));
+    match tiles.first() {
+        Some(first) => tiles.iter().filter(|tile| size(tile) != size(first)).collect(),
+        None => vec![]
+    }
 }
 
 // -- problems
@@ -666,8 +641,15 @@ fn part2(tiles: &Vec<&Tile>) -> usize {
 fn main() {
     env_logger::init();
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let tiles = parse_input(&input).unwrap().1;
-    let tiles_by_ref: Vec<&Tile> = tiles.iter().collect();
+    let tiles = parse_input.parse_complete(input.as_bytes())
+        .unwrap_or_else(|e| panic!("{}", e));
+    let odd_sized = odd_sized_tiles(&tiles);
+    for tile in odd_sized.iter() {
+        let (line, col) = tile.locate(input.as_bytes());
+        eprintln!("line {}, column {}: tile {} is a different size from the others", line, col, tile.id);
+    }
+    assert!(odd_sized.is_empty(), "{} tiles are a different size from the others", odd_sized.len());
+    let tiles_by_ref: Vec<&Tile> = tiles.iter().map(|tile| &tile.value).collect();
     println!("part 1 {:?}", part1(&tiles_by_ref));
     println!("part 2 {:?}", part2(&tiles_by_ref));
 }
@@ -686,9 +668,9 @@ mod tests {
 
     fn example_tiles() -> Vec<Tile> {
         let input = example_input();
-        let tiles = parse_input(input.as_str());
+        let tiles = parse_input(input.as_bytes());
         assert!(tiles.is_ok());
-        tiles.unwrap().1        
+        tiles.unwrap().1.into_iter().map(|tile| tile.value).collect()
     }
 
     #[test]
@@ -703,15 +685,33 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_corrupt_tile_is_located() {
+        let input = "Tile 1:\n#.#\n.x.\n#.#\n";
+        let err = parse_input(input.as_bytes()).unwrap_err().locate(input.as_bytes());
+        assert_eq!((err.line, err.column), (3, 2));
+        assert_eq!(err.expected, vec!["`#` or `.`"]);
+    }
+
+    #[test]
+    fn test_odd_sized_tiles() {
+        let input = "Tile 1:\n#.#\n...\n#.#\n\nTile 2:\n##\n##\n\nTile 3:\n...\n###\n...\n";
+        let tiles = parse_input(input.as_bytes()).unwrap().1;
+        let odd_sized: Vec<(TileID, usize)> = odd_sized_tiles(&tiles).iter()
+            .map(|tile| (tile.id, tile.locate(input.as_bytes()).0))
+            .collect();
+        assert_eq!(odd_sized, vec![(2, 6)]);
+    }
+
     #[test]
     fn test_orientations_iter() {
-        let ors: Vec<Orientation> = Orientation::iter().collect();
+        let ors: Vec<Orientation> = Orientation::all().collect();
         assert_eq!(ors.len(), 8);
     }
 
     #[test]
     fn test_orientations() {
-        use Orientation::*;
+        use Edge::*;
         let tile = Tile {
             id: 1,
             top: 0x2F9,
@@ -721,40 +721,68 @@ mod tests {
             content: vec![]
         };
         
-        assert_eq!(tile.top_edge_in_orientation(R0), 0x2F9);
-        assert_eq!(tile.bottom_edge_in_orientation(R0), 0x077);
-        assert_eq!(tile.left_edge_in_orientation(R0), 0x325);
-        assert_eq!(tile.right_edge_in_orientation(R0), 0x16D);
+        assert_eq!(tile.edge_in_orientation(Top, Transform::IDENTITY), 0x2F9);
+        assert_eq!(tile.edge_in_orientation(Bottom, Transform::IDENTITY), 0x077);
+        assert_eq!(tile.edge_in_orientation(Left, Transform::IDENTITY), 0x325);
+        assert_eq!(tile.edge_in_orientation(Right, Transform::IDENTITY), 0x16D);
+
+        assert_eq!(tile.edge_in_orientation(Top, Transform::ROTATE_LEFT), 0x16D);
+        assert_eq!(tile.edge_in_orientation(Bottom, Transform::ROTATE_LEFT), 0x325);
+        assert_eq!(tile.edge_in_orientation(Left, Transform::ROTATE_LEFT), 0x27D);
+        assert_eq!(tile.edge_in_orientation(Right, Transform::ROTATE_LEFT), 0x3B8);
+
+        assert_eq!(tile.edge_in_orientation(Top, Transform::ROTATE_180), 0x3B8);
+        assert_eq!(tile.edge_in_orientation(Bottom, Transform::ROTATE_180), 0x27D);
+        assert_eq!(tile.edge_in_orientation(Left, Transform::ROTATE_180), 0x2DA);
+        assert_eq!(tile.edge_in_orientation(Right, Transform::ROTATE_180), 0x293);
 
-        assert_eq!(tile.top_edge_in_orientation(R90), 0x16D);
-        assert_eq!(tile.bottom_edge_in_orientation(R90), 0x325);
-        assert_eq!(tile.left_edge_in_orientation(R90), 0x27D);
-        assert_eq!(tile.right_edge_in_orientation(R90), 0x3B8);
+        assert_eq!(tile.edge_in_orientation(Top, Transform::ROTATE_RIGHT), 0x293);
+        assert_eq!(tile.edge_in_orientation(Bottom, Transform::ROTATE_RIGHT), 0x2DA);
+        assert_eq!(tile.edge_in_orientation(Left, Transform::ROTATE_RIGHT), 0x077);
+        assert_eq!(tile.edge_in_orientation(Right, Transform::ROTATE_RIGHT), 0x2F9);
+    }
 
-        assert_eq!(tile.top_edge_in_orientation(R180), 0x3B8);
-        assert_eq!(tile.bottom_edge_in_orientation(R180), 0x27D);
-        assert_eq!(tile.left_edge_in_orientation(R180), 0x2DA);
-        assert_eq!(tile.right_edge_in_orientation(R180), 0x293);
+    #[test]
+    fn test_edges_match_oriented_image() {
+        let cells: Vec<Vec<char>> = example_input().lines().skip(1).take(10)
+            .map(|row| row.chars().collect())
+            .collect();
+        let tile = Tile {
+            id: 2311,
+            top: decode_row(&cells[0]),
+            bottom: decode_row(&cells[9]),
+            left: decode_column(&cells, 0),
+            right: decode_column(&cells, 9),
+            content: vec![]
+        };
 
-        assert_eq!(tile.top_edge_in_orientation(R270), 0x293);
-        assert_eq!(tile.bottom_edge_in_orientation(R270), 0x2DA);
-        assert_eq!(tile.left_edge_in_orientation(R270), 0x077);
-        assert_eq!(tile.right_edge_in_orientation(R270), 0x2F9);
+        let mut image = Image::new(&cells);
+        for orientation in Orientation::all() {
+            image.orientation = orientation;
+            let oriented: Vec<Vec<char>> = (0..10).map(|y|
+                (0..10).map(|x| *image.get(&Pos { x, y }).unwrap()).collect()
+            ).collect();
+
+            let edges = tile.edges_in_orientation(orientation);
+            assert_eq!(edges.top, decode_row(&oriented[0]));
+            assert_eq!(edges.bottom, decode_row(&oriented[9]));
+            assert_eq!(edges.left, decode_column(&oriented, 0));
+            assert_eq!(edges.right, decode_column(&oriented, 9));
+        }
     }
 
     #[test]
     fn test_allowed_neighbours() {
-        use Orientation::*;
         use Relationship::*;
 
         let tiles = example_tiles();
         let tiles_by_ref: Vec<&Tile> = tiles.iter().collect();
         let allowed_neighbours = AllowedOrientedTiles::new(&tiles_by_ref);
 
-        assert!(allowed_neighbours.get(1951, R0FlipV, Below).contains(&OrientedTile { tile_id: 2729, orientation: R0FlipV }));
-        assert!(allowed_neighbours.get(1951, R0FlipV, RightOf).contains(&OrientedTile { tile_id: 2311, orientation: R0FlipV }));
-        assert!(allowed_neighbours.get(2729, R0FlipV, Below).contains(&OrientedTile { tile_id: 2971, orientation: R0FlipV }));
-        assert!(allowed_neighbours.get(2311, R0FlipV, RightOf).contains(&OrientedTile { tile_id: 3079, orientation: R0 }));
+        assert!(allowed_neighbours.get(1951, Transform::FLIP_VERTICAL, Below).contains(&OrientedTile { tile_id: 2729, orientation: Transform::FLIP_VERTICAL }));
+        assert!(allowed_neighbours.get(1951, Transform::FLIP_VERTICAL, RightOf).contains(&OrientedTile { tile_id: 2311, orientation: Transform::FLIP_VERTICAL }));
+        assert!(allowed_neighbours.get(2729, Transform::FLIP_VERTICAL, Below).contains(&OrientedTile { tile_id: 2971, orientation: Transform::FLIP_VERTICAL }));
+        assert!(allowed_neighbours.get(2311, Transform::FLIP_VERTICAL, RightOf).contains(&OrientedTile { tile_id: 3079, orientation: Transform::IDENTITY }));
     }
 
     #[test]
@@ -35,7 +35,9 @@ impl<'a> Model<'a> {
             allergens: allergens.into_iter().collect()
         });
 
-        let foods = one_or_more(whitespace_wrap(food)).parse(input).unwrap().1;
+        let foods = one_or_more(whitespace_wrap(food))
+            .parse_complete(input)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         Model {
             foods,
@@ -129,8 +129,8 @@ impl Game {
 
 fn parse_input(input: &str) -> ParseResult<Game> {
     let player_tag = integer.between(match_literal("Player "), match_literal(":"));
-    let cards = one_or_more(whitespace_wrap(integer)).map(|cards| cards.into_iter().collect());
-    let player = right(player_tag, cards);
+    let cards = one_or_more(line(integer)).map(|cards| cards.into_iter().collect());
+    let player = section(player_tag, cards);
     let game = one_or_more(player).map(Game::new);
     game.parse(input)
 }
@@ -150,10 +150,10 @@ fn part2(game: &mut Game) -> Score {
 fn main() {
     env_logger::init();
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let mut game = parse_input(&input).unwrap().1;
+    let mut game = parse_input.parse_complete(&input).unwrap_or_else(|e| panic!("{}", e));
     println!("part 1 {}", part1(&mut game));
 
-    let mut game = parse_input(&input).unwrap().1;
+    let mut game = parse_input.parse_complete(&input).unwrap_or_else(|e| panic!("{}", e));
     println!("part 2 {}", part2(&mut game))
 }
 
@@ -1,60 +1,11 @@
-use std::collections::{HashMap, HashSet};
-use std::ops::Add;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
+use std::collections::HashSet;
+use geom::hex::{steps, Hex, HexDirection};
+use geom::{Grid as _, SparseGrid};
 use parser::*;
 
 // -- model
 
-#[derive(Debug, PartialEq, Copy, Clone, EnumIter)]
-enum Direction {
-    East,
-    West,
-    NorthEast,
-    NorthWest,
-    SouthEast,
-    SouthWest
-}
-
-type Path = Vec<Direction>;
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-struct HexTile {
-    x: i64,
-    y: i64,
-    z: i64
-}
-
-impl HexTile {
-    fn new(x: i64, y: i64, z: i64) -> Self {
-        assert!(x + y + z == 0);
-        HexTile { x, y, z }
-    }
-
-    fn from_path(path: &Path) -> Self {
-        path.iter().fold(HexTile::new(0, 0, 0), |hex, step| hex + step)
-    }
-
-    fn neighbours(&self) -> impl Iterator<Item = HexTile> + '_ {
-        Direction::iter().map(move |dir| *self + &dir)
-    }
-}
-
-impl Add<&Direction> for HexTile {
-    type Output = HexTile;
-
-    fn add(self, dir: &Direction) -> Self {
-        use Direction::*;
-        match dir {
-            East      => HexTile::new(self.x + 1, self.y - 1, self.z),
-            West      => HexTile::new(self.x - 1, self.y + 1, self.z),
-            NorthEast => HexTile::new(self.x + 1, self.y,     self.z - 1),
-            NorthWest => HexTile::new(self.x,     self.y + 1, self.z - 1),
-            SouthEast => HexTile::new(self.x,     self.y - 1, self.z + 1),
-            SouthWest => HexTile::new(self.x - 1, self.y,     self.z + 1)
-        }
-    }
-}
+type Path = Vec<HexDirection>;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 enum Color {
@@ -72,22 +23,19 @@ impl Color {
 
 #[derive(Debug, Clone)]
 struct Grid {
-    tiles: HashMap<HexTile, Color>
+    tiles: SparseGrid<Color, Hex>
 }
 
 impl Grid {
     fn new() -> Self {
-        Grid { tiles: HashMap::new() }
+        Grid { tiles: SparseGrid::new(Color::White) }
     }
 
-    fn at(&self, tile: &HexTile) -> Color {
-        match self.tiles.get(tile) {
-            None => Color::White,
-            Some(c) => *c
-        }
+    fn at(&self, tile: &Hex) -> Color {
+        self.tiles[*tile]
     }
 
-    fn flip(&mut self, tile: &HexTile) {
+    fn flip(&mut self, tile: &Hex) {
         match self.tiles.get_mut(tile) {
             None => { 
                 self.tiles.insert(*tile, Color::Black);
@@ -99,12 +47,12 @@ impl Grid {
     }
 
     fn count(&self, c: Color) -> usize {
-        self.tiles.values().filter(|t| *t == &c).count()
+        self.tiles.iter().filter(|(_, t)| *t == &c).count()
     }
 
-    fn all_tiles_with_margin(&self) -> HashSet<HexTile> {
+    fn all_tiles_with_margin(&self) -> HashSet<Hex> {
         let mut all = HashSet::new();
-        for tile in self.tiles.keys() {
+        for (tile, _) in self.tiles.iter() {
             all.insert(*tile);
             for n in tile.neighbours() {
                 all.insert(n);
@@ -136,16 +84,8 @@ impl Grid {
 
 // -- parser
 
-fn parse_paths(input: &str) -> ParseResult<Vec<Path>> {
-    let east = match_literal("e").means(Direction::East);
-    let west = match_literal("w").means(Direction::West);
-    let north_east = match_literal("ne").means(Direction::NorthEast);
-    let north_west = match_literal("nw").means(Direction::NorthWest);
-    let south_east = match_literal("se").means(Direction::SouthEast);
-    let south_west = match_literal("sw").means(Direction::SouthWest);
-    let step = east.or(west).or(north_east).or(north_west).or(south_east).or(south_west);
-    let path = one_or_more(step);
-    let paths = one_or_more(whitespace_wrap(path));
+fn parse_paths(input: &str) -> ParseResult<Recovered<Path>> {
+    let paths = recover_lines(steps());
     paths.parse(input)
 }
 
@@ -154,7 +94,7 @@ fn parse_paths(input: &str) -> ParseResult<Vec<Path>> {
 fn grid_from_paths(paths: &Vec<Path>) -> Grid {
     let mut grid = Grid::new();
     for path in paths {
-        grid.flip(&HexTile::from_path(&path));
+        grid.flip(&Hex::default().walk(path));
     }
     grid    
 }
@@ -171,7 +111,12 @@ fn part2(grid: &Grid) -> usize {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let paths = parse_paths(&input).unwrap().1;
+    let parsed = parse_paths.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
+    for error in parsed.errors.iter() {
+        eprintln!("{}", error.locate(&input));
+    }
+    let paths = parsed.records;
     let grid = grid_from_paths(&paths);
     println!("part 1 {}", part1(&grid));
     println!("part 2 {}", part2(&grid));
@@ -180,6 +125,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     fn test_paths() -> Vec<Path> {
         parse_paths(
@@ -203,7 +149,7 @@ mod tests {
             eneswnwswnwsenenwnwnwwseeswneewsenese
             neswnwewnwnwseenwseesewsenwsweewe
             wseweeenwnesenwwwswnew"
-        ).unwrap().1
+        ).unwrap().1.records
     }
 
     fn test_grid() -> Grid {
@@ -212,19 +158,62 @@ mod tests {
 
     #[test]
     fn test_parser() {
-        use Direction::*;
+        use HexDirection::*;
         let paths = parse_paths("esew\nnwwswee");
-        assert_eq!(paths, Ok(("", vec![
+        assert_eq!(paths, Ok(("", Recovered {
+            records: vec![
+                vec![East, SouthEast, West],
+                vec![NorthWest, West, SouthWest, East, East]
+            ],
+            errors: vec![]
+        })));
+    }
+
+    #[test]
+    fn test_parser_skips_bad_paths() {
+        use HexDirection::*;
+        let input = "esew\nnnw\nnwwswee";
+        let parsed = parse_paths(input).unwrap().1;
+        assert_eq!(parsed.records, vec![
             vec![East, SouthEast, West],
             vec![NorthWest, West, SouthWest, East, East]
-        ])));
+        ]);
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].locate(input).line, 2);
+    }
+
+    fn print_paths(paths: &[Path]) -> String {
+        let path = |path: &Path, out: &mut String| print_sep_by(unparser(), "").print(path, out);
+        print_lines(path).printed(paths)
+    }
+
+    #[test]
+    fn test_print_round_trips_input() {
+        let input = "esew\nnwwswee\nsesenwnenenewseeswwswswwnenewsewsw\n";
+        let paths = parse_paths(input).unwrap().1.records;
+        assert_eq!(print_paths(&paths), input);
+    }
+
+    fn path() -> impl Strategy<Value = Path> {
+        let direction = prop::sample::select(HexDirection::ALL.to_vec());
+        prop::collection::vec(direction, 1..20)
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_print_round_trip(paths in prop::collection::vec(path(), 0..10)) {
+            let text = print_paths(&paths);
+            let parsed = parse_paths(&text).unwrap().1;
+            prop_assert!(parsed.errors.is_empty());
+            prop_assert_eq!(parsed.records, paths);
+        }
     }
 
     #[test]
-    fn test_hextile_from_path() {
-        use Direction::*;
-        assert_eq!(HexTile::from_path(&vec![East, SouthWest, West]), HexTile::new(-1, 0, 1));
-        assert_eq!(HexTile::from_path(&vec![NorthWest, West, SouthWest, East, East]), HexTile::new(0, 0, 0));
+    fn test_hex_from_path() {
+        use HexDirection::*;
+        assert_eq!(Hex::default().walk(&[East, SouthWest, West]), Hex { q: -1, r: 1 });
+        assert_eq!(Hex::default().walk(&[NorthWest, West, SouthWest, East, East]), Hex::default());
     }
 
     #[test]
@@ -1,55 +1,30 @@
+use geom::{DenseGrid, Grid, Offset, Pos, Wrapping};
+use parser::*;
 
 // --- model
 
 #[derive(Debug)]
 struct Model {
-    width: usize,
-    height: usize,
-    bitmap: Vec<Vec<char>>
+    // The pattern repeats in both directions
+    bitmap: Wrapping<DenseGrid<char>>
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Pos {
-    x: usize,
-    y: usize
+fn positions_on_slope(start: Pos, offset: Offset) -> impl Iterator<Item = Pos> {
+    std::iter::successors(Some(start), move |pos| Some(*pos + offset))
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Offset {
-    x: usize,
-    y: usize
-}
-
-impl std::ops::Add<Offset> for Pos {
-    type Output = Pos;
-
-    fn add(self, offset: Offset) -> Self::Output {
-        Pos { 
-            x: self.x + offset.x,
-            y: self.y + offset.y
-        }
+impl Model {
+    fn height(&self) -> i64 {
+        self.bitmap.bounds().height()
     }
-}
 
-impl Pos {
-    fn slope(self, offset: Offset) -> impl Iterator<Item = Pos> {
-        let mut pos = self;
-        std::iter::from_fn(move || {
-            let result = pos;
-            pos = pos + offset;
-            Some(result)
-        })
-    }
-}
-
-impl Model {
     fn tree_at(&self, p: &Pos) -> bool {
-        self.bitmap[p.y % self.height][p.x % self.width] == '#'
+        self.bitmap.get(p) == Some(&'#')
     }
 
     fn count_trees_on_slope(&self, start: Pos, slope: Offset) -> usize {
-        start.slope(slope)
-            .take_while(|p| p.y < self.height)
+        positions_on_slope(start, slope)
+            .take_while(|p| p.y < self.height())
             .filter(|p| self.tree_at(&p))
             .count()
     }
@@ -58,18 +33,12 @@ impl Model {
 // --- input file
 
 fn parse_input(input: &str) -> Model {
-    let bitmap: Vec<Vec<char>> = input.lines().map(|line| line.trim().chars().collect()).collect();
-    let min_length = bitmap.iter().map(|row| row.len()).min();
-    let max_length = bitmap.iter().map(|row| row.len()).max();
-    let length = bitmap.iter().next().map(|row| row.len());
-    if length != min_length || length != max_length {
-        panic!();
-    }
+    let bitmap: Vec<Vec<char>> = grid()
+        .parse_complete(input)
+        .unwrap_or_else(|e| panic!("{}", e));
 
     Model {
-        width: length.unwrap(),
-        height: bitmap.len(),
-        bitmap
+        bitmap: Wrapping::new(DenseGrid::from_rows(bitmap))
     }
 }
 
@@ -122,10 +91,17 @@ mod tests {
     #[test]
     fn test_parse_input() {
         let model = parse_input(sample_input());
-        assert_eq!(model.width, 11);
-        assert_eq!(model.height, 11);
-        assert_eq!(model.bitmap[0][0], '.');
-        assert_eq!(model.bitmap[8][7], '#');
+        assert_eq!(model.bitmap.inner().width(), 11);
+        assert_eq!(model.bitmap.inner().height(), 11);
+        assert_eq!(model.bitmap.get(&Pos { x: 0, y: 0 }), Some(&'.'));
+        assert_eq!(model.bitmap.get(&Pos { x: 7, y: 8 }), Some(&'#'));
+        assert_eq!(model.bitmap.get(&Pos { x: 18, y: 8 }), Some(&'#'));
+    }
+
+    #[test]
+    #[should_panic(expected = "parse error at line 3, column 11: expected 11 cells in each row")]
+    fn test_parse_input_with_ragged_rows() {
+        parse_input("..##.......\n#...#...#..\n.#....#..#\n");
     }
 
     #[test]
@@ -1,9 +1,8 @@
 use std::collections::HashMap;
 use std::ops::RangeInclusive;
-use std::str::Chars;
 
-mod parser;
-use parser::*;
+use parser::compat::*;
+use parser::{any_char, count, match_literal, paragraphs, right, Parser as _};
 
 // --- model
 
@@ -33,15 +32,12 @@ fn height_string_is_valid(s: &str) -> bool {
     }
 }
 
-fn has_n_digits(mut chars: Chars, n: usize, radix: u32) -> bool {
-    (0..n).all(|_|
-        chars.next().map(|c| c.is_digit(radix)).unwrap_or(false)
-    ) && chars.next().is_none()
+fn n_digits<'a>(n: usize, radix: u32) -> impl parser::Parser<'a, Vec<char>> {
+    count(any_char.pred(move |c| c.is_digit(radix)), n)
 }
 
 fn is_valid_hair_color(s: &str) -> bool {
-    let mut chars = s.chars();
-    chars.next() == Some('#') && has_n_digits(chars, 6, 16)
+    right(match_literal("#"), n_digits(6, 16)).parse_complete(s).is_ok()
 }
 
 fn is_valid_eye_color(s: &str) -> bool {
@@ -116,7 +112,7 @@ impl PassportData {
 
     fn passport_id_is_valid(&self) -> bool {
         if let Some(s) = self.0.get("pid") {
-            has_n_digits(s.chars(), 9, 10)
+            n_digits(9, 10).parse_complete(s).is_ok()
         } else {
             false
         }
@@ -125,15 +121,24 @@ impl PassportData {
 
 // --- input file
 
+// A space or a single line end, but not the blank line after a passport
+fn field_separator(input: &str) -> ParseResult<'_, ()> {
+    let next = input.strip_prefix(' ')
+        .or_else(|| input.strip_prefix("\r\n"))
+        .or_else(|| input.strip_prefix('\n'))
+        .unwrap_or(input);
+    Ok((next, ()))
+}
+
 fn parse_input(input: &str) -> Vec<PassportData> {
     let tag = map(one_or_more(letter), |ls| ls.iter().collect());
     let value = map(one_or_more(non_whitespace), |cs| cs.iter().collect());
     let item = between(tag, string(":"), value);
-    let passport = map(one_or_more(first(item, whitespace)), PassportData::new);
+    let passport = map(one_or_more(first(item, field_separator)), PassportData::new);
 
-    input.split("\n\n")
-        .map(|inp| passport.parse(inp).unwrap().1)
-        .collect()
+    paragraphs(untagged(passport))
+        .parse_complete(input)
+        .unwrap_or_else(|e| panic!("{}", e))
 }
 
 
@@ -193,6 +198,14 @@ iyr:2011 ecl:brn hgt:59in"
         });
     }
 
+    #[test]
+    fn test_parse_input_with_crlf_and_trailing_blank_lines() {
+        let input = sample_input().replace('\n', "\r\n") + "\r\n\r\n";
+        let data = parse_input(&input);
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[3].0.get("hgt"), Some(&String::from("59in")));
+    }
+
     #[test]
     fn test_has_required_fields() {
         let data = parse_input(sample_input());
@@ -1,3 +1,4 @@
+use parser::*;
 
 // --- model
 
@@ -13,16 +14,24 @@ impl BoardingPass {
     }
 }
 
-fn decode(s: &str, one: char) -> usize {
-    s.chars().fold(0, |r, c| (r << 1) | (if c == one { 1 } else { 0 }))
+// --- parser
+
+// `n` binary digits written as `zero` and `one`
+fn bits<'a>(n: usize, zero: char, one: char) -> impl Parser<'a, usize> {
+    count(any_char.pred(move |c| *c == zero || *c == one), n)
+        .map(move |cs| cs.into_iter().fold(0, |r, c| (r << 1) | (if c == one { 1 } else { 0 })))
 }
 
-impl From<&str> for BoardingPass {
-    fn from(s: &str) -> BoardingPass {
-        let row = decode(&s[0..7], 'B');
-        let column = decode(&s[7..10], 'R');
-        BoardingPass { row, column }
-    }
+fn boarding_pass(input: &str) -> ParseResult<'_, BoardingPass> {
+    pair(
+        bits(7, 'F', 'B').label("row"),
+        bits(3, 'L', 'R').label("column"),
+        |row, column| BoardingPass { row, column }
+    ).parse(input)
+}
+
+fn parse_input(input: &str) -> ParseResult<'_, Vec<BoardingPass>> {
+    one_or_more(line(boarding_pass)).parse(input)
 }
 
 // --- problems
@@ -44,7 +53,8 @@ fn part2(passes: &Vec<BoardingPass>) -> Option<usize> {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let passes: Vec<BoardingPass> = input.lines().map(|line| line.into()).collect();
+    let passes = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
 
     println!("part1 {}", part1(&passes).unwrap());
     println!("part2 {}", part2(&passes).unwrap());
@@ -56,16 +66,26 @@ mod tests {
 
     #[test]
     fn test_deocde() {
-        assert_eq!(decode("BFFFBBF", 'B'), 70);
-        assert_eq!(decode("RRR", 'R'), 7);
-        assert_eq!(decode("FFFBBBF", 'B'), 14);
-        assert_eq!(decode("BBFFBBF", 'B'), 102);
+        assert_eq!(bits(7, 'F', 'B').parse("BFFFBBF"), Ok(("", 70)));
+        assert_eq!(bits(3, 'L', 'R').parse("RRR"), Ok(("", 7)));
+        assert_eq!(bits(7, 'F', 'B').parse("FFFBBBF"), Ok(("", 14)));
+        assert_eq!(bits(7, 'F', 'B').parse("BBFFBBF"), Ok(("", 102)));
     }
 
     #[test]
     fn test_to_baording_pass() {
-        assert_eq!(BoardingPass::from("BFFFBBFRRR"), BoardingPass { row: 70, column: 7 });
-        assert_eq!(BoardingPass::from("FFFBBBFRRR"), BoardingPass { row: 14, column: 7 });
-        assert_eq!(BoardingPass::from("BBFFBBFRLL"), BoardingPass { row: 102, column: 4 });
+        assert_eq!(boarding_pass("BFFFBBFRRR"), Ok(("", BoardingPass { row: 70, column: 7 })));
+        assert_eq!(boarding_pass("FFFBBBFRRR"), Ok(("", BoardingPass { row: 14, column: 7 })));
+        assert_eq!(boarding_pass("BBFFBBFRLL"), Ok(("", BoardingPass { row: 102, column: 4 })));
+    }
+
+    #[test]
+    fn test_short_boarding_pass() {
+        let error = boarding_pass.parse_complete("BFFFBBRRR").unwrap_err();
+        assert_eq!(error.column, 7);
+        assert_eq!(error.context, vec!["row"]);
+
+        let error = parse_input.parse_complete("BFFFBBFRRR\nBFFFBBRRR\n").unwrap_err();
+        assert_eq!(error.line, 2);
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use parser::*;
 
 // --- model
 
@@ -10,10 +11,7 @@ struct Group {
 
 impl From<&str> for Group {
     fn from(s: &str) -> Self {
-        let people = s.lines()
-                        .map(|line| line.chars().collect())
-                        .collect();
-        Group { people }
+        group.parse_complete(s).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -36,6 +34,19 @@ impl Group {
     }
 }
 
+// --- parser
+
+fn group(input: &str) -> ParseResult<'_, Group> {
+    let person = one_or_more(letter).map(|answers| answers.into_iter().collect());
+    one_or_more(line(person))
+        .map(|people| Group { people })
+        .parse(input)
+}
+
+fn parse_input(input: &str) -> ParseResult<'_, Vec<Group>> {
+    paragraphs(group).parse(input)
+}
+
 // --- problems 
 
 fn part1(groups: &Vec<Group>) -> usize {
@@ -48,7 +59,8 @@ fn part2(groups: &Vec<Group>) -> usize {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let groups: Vec<Group> = input.split("\n\n").map(Group::from).collect();
+    let groups = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
 
     println!("part1 {}", part1(&groups));
     println!("part2 {}", part2(&groups));
@@ -58,6 +70,12 @@ fn main() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_input() {
+        let groups = parse_input("abc\n\na\nb\nc\n\n\nab\r\nac\r\n\r\n").unwrap().1;
+        assert_eq!(groups.iter().map(|g| g.people.len()).collect::<Vec<_>>(), vec![1, 3, 2]);
+    }
+
     #[test]
     fn test_anyone_yesses() {
         assert_eq!(Group::from("abc").anyone_yesses(), 3);
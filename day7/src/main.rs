@@ -36,16 +36,21 @@ fn parse_rule<'a>() -> impl Parser<'a, ContainsRule> {
         let color = one_or_more(letter).map(|ls| ls.into_iter().collect());
 
         pair(whitespace_wrap(adjective), color, |a, c| BagColor(a, c))
+            .label("bag color")
     }
 
     fn container<'b>() -> impl Parser<'b, BagColor> {
         left(bag_color(), match_literal(" bags contain "))
     }
 
+    // both kinds of rule start with the container, so only parse it once
+    let container = container().memoized();
+
     let bag_or_bags = match_literal(" bags, ").or(match_literal(" bag, ")).or(match_literal(" bags.")).or(match_literal(" bag."));
-    let contained = pair(whitespace_wrap(integer), left(bag_color(), bag_or_bags), |n, c| (n, c));
+    let contained = pair(whitespace_wrap(integer), left(bag_color(), bag_or_bags), |n, c| (n, c))
+        .label("bag contents");
 
-    let contents_rule = pair(container(), one_or_more(contained), |color, contents| 
+    let contents_rule = pair(container.clone(), one_or_more(contained), |color, contents| 
         ContainsRule {
             container: color.clone(),
             contents: contents.iter().map(|(n, c)| Content {
@@ -55,18 +60,18 @@ fn parse_rule<'a>() -> impl Parser<'a, ContainsRule> {
         }
     );
 
-    let no_contents_rule = left(container(), match_literal("no other bags.")).map(|color| 
+    let no_contents_rule = left(container, match_literal("no other bags.")).map(|color| 
         ContainsRule {
             container: color,
             contents: vec![]
         }
     );
     
-    contents_rule.or(no_contents_rule)
+    contents_rule.or(no_contents_rule).label("bag rule")
 }
 
 fn parse_input(input: &str) -> ParseResult<RuleSet> {
-    let rule_set = one_or_more(whitespace_wrap(parse_rule()));
+    let rule_set = many_till(whitespace_wrap(parse_rule()), eof());
 
     rule_set.parse(input).map(|(rest, rules)| {
         let rule_set = RuleSet { 
@@ -76,6 +81,34 @@ fn parse_input(input: &str) -> ParseResult<RuleSet> {
     })
 }
 
+// --- printer
+
+impl Unparse for BagColor {
+    fn unparse(&self, out: &mut String) {
+        out.push_str(&format!("{} {}", self.0, self.1));
+    }
+}
+
+impl Unparse for Content {
+    fn unparse(&self, out: &mut String) {
+        let bags = if self.count == 1 { "bag" } else { "bags" };
+        out.push_str(&format!("{} {} {}", self.count, self.color.unparsed(), bags));
+    }
+}
+
+impl Unparse for ContainsRule {
+    fn unparse(&self, out: &mut String) {
+        self.container.unparse(out);
+        out.push_str(" bags contain ");
+        if self.contents.is_empty() {
+            out.push_str("no other bags");
+        } else {
+            print_sep_by(unparser(), ", ").print(&self.contents[..], out);
+        }
+        out.push('.');
+    }
+}
+
 impl RuleSet {
     fn can_contain(&self, from: &BagColor, to: &BagColor) -> bool {
         self.rules.get(from)
@@ -114,7 +147,8 @@ impl RuleSet {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let rules: RuleSet = parse_input(&input).unwrap().1;
+    let rules: RuleSet = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
 
     println!("part1 {}", rules.part1());
     println!("part2 {}", rules.part2());
@@ -124,6 +158,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_parse_with_single_clause() {
@@ -179,9 +214,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_error_context() {
+        let input = "light red bags contain 1 bright white pouch.";
+        let error = parse_rule().parse(input).unwrap_err().locate(input);
+        assert_eq!(error.column, 38);
+        assert_eq!(error.context, vec!["bag rule", "bag contents"]);
+        assert_eq!(error.expected, vec!["` bags, `", "` bag, `", "` bags.`", "` bag.`"]);
+    }
+
+    #[test]
+    fn test_parse_input_rejects_truncated_rules() {
+        let input = "faded blue bags contain no other bags.\nlight red bags contain 1 bright white pouch.\n";
+        let error = parse_input.parse_complete(input).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 38));
+        assert_eq!(error.found, " pouch.");
+        assert!(error.expected.contains(&"` bag.`".to_string()));
+    }
+
     #[test]
     fn test_parse_records_separated_by_lines() {
         let p = one_or_more(whitespace_wrap(any_char));
         assert_eq!(p.parse("a\nb\nc\n"), Ok(("", vec!['a', 'b', 'c'])));
     }
+
+    #[test]
+    fn test_print_round_trips_input() {
+        for line in [
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.",
+            "bright white bags contain 1 shiny gold bag.",
+            "faded blue bags contain no other bags."
+        ].iter() {
+            let rule = parse_rule().parse(line).unwrap().1;
+            assert_eq!(&rule.unparsed(), line);
+        }
+    }
+
+    fn bag_color() -> impl Strategy<Value = BagColor> {
+        ("[a-z]{1,8}", "[a-z]{1,8}").prop_map(|(adj, col)| BagColor(adj, col))
+    }
+
+    fn rule() -> impl Strategy<Value = ContainsRule> {
+        let content = (bag_color(), 0..100usize).prop_map(|(color, count)| Content { color, count });
+        (bag_color(), prop::collection::vec(content, 0..5))
+            .prop_map(|(container, contents)| ContainsRule { container, contents })
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_print_round_trip(rule in rule()) {
+            let text = rule.unparsed();
+            prop_assert_eq!(parse_rule().parse(&text), Ok(("", rule)));
+        }
+    }
 }
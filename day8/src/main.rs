@@ -61,21 +61,44 @@ impl Machine {
 
 // --- parser
 
-fn parse_input(input: &str) -> ParseResult<Program> {
-    let sign = either(
-        any_char.pred(|c| *c == '+').means(1),
-        any_char.pred(|c| *c == '-').means(-1)
-    );
-    let signed_integer = pair(sign, integer, |s, i| s * i).boxed();
-
-    let acc = right(match_literal("acc "), signed_integer.clone()).map(Instruction::Acc);
-    let jmp = right(match_literal("jmp "), signed_integer.clone()).map(Instruction::Jmp);
-    let nop = right(match_literal("nop "), signed_integer).map(Instruction::Nop);
-    let instruction = whitespace_wrap(one_of3(acc, jmp, nop));
-
-    zero_or_more(instruction).parse(input)
+fn parse_input(input: &str) -> ParseResult<Recovered<Spanned<Instruction>>> {
+    // operands always have a sign, as in `+0` and `-99`
+    let operand = || int::<i64>().sign_required();
+    let acc = right(match_literal("acc "), operand()).map(Instruction::Acc);
+    let jmp = right(match_literal("jmp "), operand()).map(Instruction::Jmp);
+    let nop = right(match_literal("nop "), operand()).map(Instruction::Nop);
+    let instruction = one_of3(acc, jmp, nop);
+
+    recover_lines(instruction.spanned()).parse(input)
+}
+
+// Jumps which leave the program anywhere other than just past its end
+fn invalid_jumps(program: &[Spanned<Instruction>]) -> Vec<&Spanned<Instruction>> {
+    program.iter()
+        .enumerate()
+        .filter(|(index, instr)| match instr.value {
+            Instruction::Jmp(arg) => {
+                let target = *index as i64 + arg;
+                target < 0 || target > program.len() as i64
+            }
+            _ => false
+        })
+        .map(|(_, instr)| instr)
+        .collect()
 }
 
+// --- printer
+
+impl Unparse for Instruction {
+    fn unparse(&self, out: &mut String) {
+        let (operation, argument) = match self {
+            Instruction::Acc(arg) => ("acc", arg),
+            Instruction::Jmp(arg) => ("jmp", arg),
+            Instruction::Nop(arg) => ("nop", arg)
+        };
+        out.push_str(&format!("{} {:+}", operation, argument));
+    }
+}
 
 // --- problems
 
@@ -108,7 +131,16 @@ fn part2(program: &Program) -> Option<i64> {
 
 fn main() {
     let input = std::fs::read_to_string("./input.txt").unwrap();
-    let program: Program = parse_input(&input).unwrap().1;
+    let parsed = parse_input.parse_complete(&input)
+        .unwrap_or_else(|e| panic!("{}", e));
+    for error in parsed.errors.iter() {
+        eprintln!("{}", error.locate(&input));
+    }
+    for jump in invalid_jumps(&parsed.records) {
+        let (line, col) = jump.locate(input.as_str());
+        eprintln!("line {}, column {}: `{}` jumps outside the program", line, col, jump.unparsed());
+    }
+    let program: Program = parsed.records.into_iter().map(|instr| instr.value).collect();
 
     println!("part1 {:?}", part1(&program));
     println!("part2 {:?}", part2(&program));
@@ -117,6 +149,11 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    fn values(records: Vec<Spanned<Instruction>>) -> Program {
+        records.into_iter().map(|instr| instr.value).collect()
+    }
 
     fn test_program() -> Program {
         vec![
@@ -145,9 +182,58 @@ mod tests {
             jmp -4
             acc +6
         ";
-        let instructions = parse_input(sample);
+        let (rest, parsed) = parse_input(sample).unwrap();
 
-        assert_eq!(instructions, Ok(("", test_program())));
+        assert_eq!(rest, "");
+        assert!(parsed.errors.is_empty());
+        assert_eq!(values(parsed.records), test_program());
+    }
+
+    #[test]
+    fn test_parse_continues_past_bad_instructions() {
+        let sample = "nop +0\nacc 1\njmp +4\nhcf -1\nacc +6\n";
+        let (_, parsed) = parse_input(sample).unwrap();
+
+        assert_eq!(
+            parsed.records.iter().map(|instr| instr.locate(sample).0).collect::<Vec<_>>(),
+            vec![1, 3, 5]
+        );
+        assert_eq!(values(parsed.records), vec![Instruction::Nop(0), Instruction::Jmp(4), Instruction::Acc(6)]);
+        assert_eq!(
+            parsed.errors.iter().map(|e| e.locate(sample).line).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+    }
+
+    #[test]
+    fn test_print_round_trips_input() {
+        let input = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6\n";
+        let mut program = values(parse_input(input).unwrap().1.records);
+        assert_eq!(print_lines(unparser()).printed(&program[..]), input);
+
+        program[7] = Instruction::Nop(-4);
+        assert_eq!(
+            print_lines(unparser()).printed(&program[..]),
+            input.replace("jmp -4", "nop -4")
+        );
+    }
+
+    fn instruction() -> impl Strategy<Value = Instruction> {
+        prop_oneof![
+            any::<i64>().prop_map(Instruction::Acc),
+            any::<i64>().prop_map(Instruction::Jmp),
+            any::<i64>().prop_map(Instruction::Nop)
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_print_round_trip(program in prop::collection::vec(instruction(), 0..20)) {
+            let text = print_lines(unparser()).printed(&program[..]);
+            let parsed = parse_input(&text).unwrap().1;
+            prop_assert!(parsed.errors.is_empty());
+            prop_assert_eq!(values(parsed.records), program);
+        }
     }
 
     #[test]
@@ -159,6 +245,16 @@ mod tests {
         assert_eq!(machine.accumulator, 5);
     }
 
+    #[test]
+    fn test_invalid_jumps() {
+        let input = "jmp +2\nnop +0\njmp -3\nacc +1\n  jmp +2\n";
+        let parsed = parse_input(input).unwrap().1;
+        let invalid: Vec<(usize, usize)> = invalid_jumps(&parsed.records).iter()
+            .map(|jump| jump.locate(input))
+            .collect();
+        assert_eq!(invalid, vec![(3, 1), (5, 3)]);
+    }
+
     #[test]
     fn test_part2() {
         assert_eq!(part2(&test_program()), Some(8));        
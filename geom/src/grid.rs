@@ -0,0 +1,408 @@
+use super::Pos;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Index, IndexMut};
+
+// A place for a cell: a square on a plane, a point on a lattice of any number
+// of dimensions, or a hexagonal tile. Each kind has its own neighbourhood and
+// its own shape of bounds.
+pub trait Position: Copy + Eq + Hash {
+    type Bounds: Region<Self>;
+
+    // The positions touching this one
+    fn neighbours(self) -> impl Iterator<Item = Self>;
+}
+
+// The smallest region of some shape containing a set of positions, grown by
+// including each of them. The default region is empty.
+pub trait Region<P>: Copy + Default {
+    fn contains(&self, pos: &P) -> bool;
+
+    fn include(&mut self, pos: P);
+
+    // Every position inside, in a fixed order
+    fn positions(self) -> impl Iterator<Item = P>;
+}
+
+// The eight surrounding squares, diagonals included
+impl Position for Pos {
+    type Bounds = Bounds;
+
+    fn neighbours(self) -> impl Iterator<Item = Pos> {
+        Pos::neighbours(self)
+    }
+}
+
+// The smallest rectangle containing a set of positions, like `BoundsN` for
+// the two dimensional `Pos`. An empty rectangle contains nothing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Bounds {
+    pub min: Pos,
+    pub max: Pos
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds::empty()
+    }
+}
+
+impl Bounds {
+    pub fn empty() -> Self {
+        Bounds {
+            min: Pos { x: i64::MAX, y: i64::MAX },
+            max: Pos { x: i64::MIN, y: i64::MIN }
+        }
+    }
+
+    // From the origin to `width` by `height`, or empty if either is zero
+    pub fn with_size(width: i64, height: i64) -> Self {
+        if width <= 0 || height <= 0 {
+            return Bounds::empty();
+        }
+        Bounds {
+            min: Pos::default(),
+            max: Pos { x: width - 1, y: height - 1 }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y
+    }
+
+    pub fn width(&self) -> i64 {
+        if self.is_empty() { 0 } else { self.max.x - self.min.x + 1 }
+    }
+
+    pub fn height(&self) -> i64 {
+        if self.is_empty() { 0 } else { self.max.y - self.min.y + 1 }
+    }
+
+    pub fn contains(&self, pos: &Pos) -> bool {
+        self.min.x <= pos.x && pos.x <= self.max.x && self.min.y <= pos.y && pos.y <= self.max.y
+    }
+
+    pub fn include(&mut self, pos: Pos) {
+        self.min = Pos { x: self.min.x.min(pos.x), y: self.min.y.min(pos.y) };
+        self.max = Pos { x: self.max.x.max(pos.x), y: self.max.y.max(pos.y) };
+    }
+
+    // Every position inside, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let bounds = *self;
+        (bounds.min.y..=bounds.max.y).flat_map(move |y|
+            (bounds.min.x..=bounds.max.x).map(move |x| Pos { x, y })
+        )
+    }
+}
+
+impl Region<Pos> for Bounds {
+    fn contains(&self, pos: &Pos) -> bool {
+        Bounds::contains(self, pos)
+    }
+
+    fn include(&mut self, pos: Pos) {
+        Bounds::include(self, pos)
+    }
+
+    fn positions(self) -> impl Iterator<Item = Pos> {
+        Bounds::positions(&self)
+    }
+}
+
+// Cells indexed by position. Positions with no cell are outside the grid:
+// `get` and `get_mut` both find a cell at exactly the positions inside it.
+pub trait Grid {
+    type Position: Position;
+    type Cell;
+
+    fn get(&self, pos: &Self::Position) -> Option<&Self::Cell>;
+
+    fn get_mut(&mut self, pos: &Self::Position) -> Option<&mut Self::Cell>;
+
+    // The region the cells are in
+    fn bounds(&self) -> <Self::Position as Position>::Bounds;
+
+    // Every position within the bounds, in the order the bounds give them
+    fn iter_positions(&self) -> impl Iterator<Item = Self::Position> {
+        self.bounds().positions()
+    }
+
+    // The neighbouring positions which are in the grid
+    fn neighbours(&self, pos: &Self::Position) -> impl Iterator<Item = Self::Position> + use<'_, Self> {
+        pos.neighbours().filter(move |n| self.get(n).is_some())
+    }
+}
+
+// A rectangle of cells from the origin, stored row after row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseGrid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>
+}
+
+impl<T> DenseGrid<T> {
+    pub fn from_fn<F>(width: usize, height: usize, mut f: F) -> Self
+    where
+        F: FnMut(Pos) -> T
+    {
+        DenseGrid {
+            width,
+            height,
+            cells: Bounds::with_size(width as i64, height as i64).positions().map(&mut f).collect()
+        }
+    }
+
+    // Rows must all be the same length
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+        let width = rows.first().map_or(0, Vec::len);
+        assert!(rows.iter().all(|row| row.len() == width), "grid rows have different lengths");
+        DenseGrid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect()
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Every cell, row by row
+    pub fn cells(&self) -> impl Iterator<Item = &T> + '_ {
+        self.cells.iter()
+    }
+
+    fn index_of(&self, pos: &Pos) -> Option<usize> {
+        if 0 <= pos.x && (pos.x as usize) < self.width && 0 <= pos.y && (pos.y as usize) < self.height {
+            Some(pos.y as usize * self.width + pos.x as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Grid for DenseGrid<T> {
+    type Position = Pos;
+    type Cell = T;
+
+    fn get(&self, pos: &Pos) -> Option<&T> {
+        self.index_of(pos).map(|i| &self.cells[i])
+    }
+
+    fn get_mut(&mut self, pos: &Pos) -> Option<&mut T> {
+        self.index_of(pos).map(move |i| &mut self.cells[i])
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::with_size(self.width as i64, self.height as i64)
+    }
+}
+
+// Panics outside the grid, like indexing a Vec
+impl<T> Index<Pos> for DenseGrid<T> {
+    type Output = T;
+    fn index(&self, pos: Pos) -> &T {
+        self.get(&pos).unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for DenseGrid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(&pos).unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+// An unbounded grid of any kind of position, where every cell starts as
+// `default`. Only cells which have been inserted are stored, and the bounds
+// grow to take them in. As a `Grid` it holds just the stored cells, so looking
+// at a cell never stores it; indexing reads `default` for the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T, P: Position = Pos> {
+    cells: HashMap<P, T>,
+    default: T,
+    bounds: P::Bounds
+}
+
+impl<T, P: Position> SparseGrid<T, P> {
+    pub fn new(default: T) -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+            default,
+            bounds: P::Bounds::default()
+        }
+    }
+
+    pub fn insert(&mut self, pos: P, cell: T) {
+        self.bounds.include(pos);
+        self.cells.insert(pos, cell);
+    }
+
+    // The cells which have been inserted
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T)> + '_ {
+        self.cells.iter()
+    }
+}
+
+impl<T, P: Position> Grid for SparseGrid<T, P> {
+    type Position = P;
+    type Cell = T;
+
+    fn get(&self, pos: &P) -> Option<&T> {
+        self.cells.get(pos)
+    }
+
+    fn get_mut(&mut self, pos: &P) -> Option<&mut T> {
+        self.cells.get_mut(pos)
+    }
+
+    fn bounds(&self) -> P::Bounds {
+        self.bounds
+    }
+}
+
+// Never panics, as every position has a cell
+impl<T, P: Position> Index<P> for SparseGrid<T, P> {
+    type Output = T;
+    fn index(&self, pos: P) -> &T {
+        self.cells.get(&pos).unwrap_or(&self.default)
+    }
+}
+
+// A grid repeated in every direction, so positions off one edge wrap round
+// to the other, as on a torus. Its bounds are those of the repeated grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wrapping<G> {
+    inner: G
+}
+
+impl<G: Grid<Position = Pos>> Wrapping<G> {
+    pub fn new(inner: G) -> Self {
+        Wrapping { inner }
+    }
+
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+
+    // The position inside the repeated grid standing for `pos`
+    pub fn wrap(&self, pos: &Pos) -> Pos {
+        let bounds = self.inner.bounds();
+        if bounds.is_empty() {
+            return *pos;
+        }
+        Pos {
+            x: bounds.min.x + (pos.x - bounds.min.x).rem_euclid(bounds.width()),
+            y: bounds.min.y + (pos.y - bounds.min.y).rem_euclid(bounds.height())
+        }
+    }
+}
+
+impl<G: Grid<Position = Pos>> Grid for Wrapping<G> {
+    type Position = Pos;
+    type Cell = G::Cell;
+
+    fn get(&self, pos: &Pos) -> Option<&G::Cell> {
+        self.inner.get(&self.wrap(pos))
+    }
+
+    fn get_mut(&mut self, pos: &Pos) -> Option<&mut G::Cell> {
+        let pos = self.wrap(pos);
+        self.inner.get_mut(&pos)
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.inner.bounds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::{Hex, HexBounds};
+    use crate::{BoundsN, PosN};
+
+    fn letters() -> DenseGrid<char> {
+        DenseGrid::from_rows(vec![vec!['a', 'b', 'c'], vec!['d', 'e', 'f']])
+    }
+
+    #[test]
+    fn dense_grids() {
+        let mut grid = letters();
+        assert_eq!(grid.bounds(), Bounds { min: Pos { x: 0, y: 0 }, max: Pos { x: 2, y: 1 } });
+        assert_eq!(grid.get(&Pos { x: 2, y: 0 }), Some(&'c'));
+        assert_eq!(grid.get(&Pos { x: 3, y: 0 }), None);
+        assert_eq!(grid.get(&Pos { x: 0, y: -1 }), None);
+
+        *grid.get_mut(&Pos { x: 1, y: 1 }).unwrap() = 'E';
+        grid[Pos { x: 0, y: 0 }] = 'A';
+        assert_eq!(grid.cells().collect::<String>(), "AbcdEf");
+        assert_eq!(grid.iter_positions().map(|p| grid[p]).collect::<String>(), "AbcdEf");
+        assert_eq!(grid.neighbours(&Pos { x: 0, y: 0 }).count(), 3);
+        assert_eq!(grid.neighbours(&Pos { x: 1, y: 0 }).count(), 5);
+
+        let sums = DenseGrid::from_fn(3, 2, |p| p.x + p.y);
+        assert_eq!(sums.cells().copied().collect::<Vec<_>>(), vec![0, 1, 2, 1, 2, 3]);
+        assert!(DenseGrid::<u8>::from_rows(vec![]).bounds().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the grid")]
+    fn dense_grids_panic_when_indexed_outside() {
+        let _ = letters()[Pos { x: 3, y: 1 }];
+    }
+
+    #[test]
+    fn sparse_grids_grow() {
+        let mut grid: SparseGrid<char> = SparseGrid::new('.');
+        assert!(grid.bounds().is_empty());
+        assert_eq!(grid[Pos { x: 100, y: -100 }], '.');
+        assert_eq!(grid.get(&Pos { x: 100, y: -100 }), None);
+        assert_eq!(grid.get_mut(&Pos { x: 100, y: -100 }), None);
+        assert!(grid.bounds().is_empty());
+
+        grid.insert(Pos { x: -2, y: 1 }, '#');
+        grid.insert(Pos { x: 3, y: 4 }, '+');
+        *grid.get_mut(&Pos { x: 3, y: 4 }).unwrap() = '#';
+        assert_eq!(grid.bounds(), Bounds { min: Pos { x: -2, y: 1 }, max: Pos { x: 3, y: 4 } });
+        assert_eq!(grid.iter_positions().count(), 24);
+        assert_eq!(grid.iter_positions().filter(|p| grid[*p] == '#').count(), 2);
+        assert_eq!(grid.neighbours(&Pos { x: 50, y: 50 }).count(), 0);
+        assert_eq!(grid.neighbours(&Pos { x: -1, y: 2 }).collect::<Vec<_>>(), vec![Pos { x: -2, y: 1 }]);
+    }
+
+    #[test]
+    fn sparse_grids_of_other_positions() {
+        let mut cubes: SparseGrid<bool, PosN<3>> = SparseGrid::new(false);
+        cubes.insert(PosN([1, 1, 1]), true);
+        cubes.insert(PosN([0, 2, 1]), true);
+        assert_eq!(cubes.bounds(), BoundsN { min: PosN([0, 1, 1]), max: PosN([1, 2, 1]) });
+        assert_eq!(cubes.iter_positions().count(), 4);
+        assert_eq!(cubes.neighbours(&PosN([0, 0, 0])).filter(|p| cubes[*p]).count(), 1);
+
+        let mut tiles: SparseGrid<char, Hex> = SparseGrid::new('w');
+        tiles.insert(Hex { q: 1, r: -1 }, 'b');
+        tiles.insert(Hex { q: -1, r: 0 }, 'b');
+        assert_eq!(tiles.bounds(), HexBounds { min: Hex { q: -1, r: -1 }, max: Hex { q: 1, r: 0 } });
+        assert_eq!(tiles.iter_positions().count(), 6);
+        assert_eq!(tiles.neighbours(&Hex::default()).filter(|h| tiles[*h] == 'b').count(), 2);
+    }
+
+    #[test]
+    fn wrapping_grids_repeat() {
+        let mut grid = Wrapping::new(letters());
+        assert_eq!(grid.get(&Pos { x: 3, y: 0 }), Some(&'a'));
+        assert_eq!(grid.get(&Pos { x: -1, y: -1 }), Some(&'f'));
+        assert_eq!(grid.get(&Pos { x: 7, y: 5 }), Some(&'e'));
+        assert_eq!(grid.neighbours(&Pos { x: 0, y: 0 }).count(), 8);
+
+        *grid.get_mut(&Pos { x: 5, y: 2 }).unwrap() = 'C';
+        assert_eq!(grid.inner()[Pos { x: 2, y: 0 }], 'C');
+        assert_eq!(grid.iter_positions().count(), 6);
+    }
+}
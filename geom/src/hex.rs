@@ -0,0 +1,424 @@
+use super::{Position, Region};
+#[cfg(feature = "parse")]
+use parser::{one_or_more, Parse, Parser, Unparse};
+use std::convert::TryFrom;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+// Hexagonal grids, after https://www.redblobgames.com/grids/hexagons/
+//
+// `Hex` is a position (or a step between positions) in axial coordinates.
+// Hexes are pointy-topped, so rows run east to west and r grows towards the
+// south. The third cube coordinate, s, is implied by q + r + s = 0, so every
+// `Hex` is valid; `Cube` spells all three out for code which wants them.
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hex {
+    pub q: i64,
+    pub r: i64
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub q: i64,
+    pub r: i64,
+    pub s: i64
+}
+
+// The six neighbouring directions, anticlockwise from east, written as in
+// "esenee" with the `parse` feature
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "parse", derive(Parse, Unparse))]
+pub enum HexDirection {
+    #[cfg_attr(feature = "parse", parse("e"))]
+    East,
+    #[cfg_attr(feature = "parse", parse("ne"))]
+    NorthEast,
+    #[cfg_attr(feature = "parse", parse("nw"))]
+    NorthWest,
+    #[cfg_attr(feature = "parse", parse("w"))]
+    West,
+    #[cfg_attr(feature = "parse", parse("sw"))]
+    SouthWest,
+    #[cfg_attr(feature = "parse", parse("se"))]
+    SouthEast
+}
+
+// Rectangular storage for hex grids, where alternate rows (r layouts, for
+// pointy-topped hexes) or columns (q layouts, for flat-topped hexes) are
+// shoved half a hex along
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OffsetLayout {
+    OddR,
+    EvenR,
+    OddQ,
+    EvenQ
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OffsetCoord {
+    pub col: i64,
+    pub row: i64
+}
+
+impl HexDirection {
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East, HexDirection::NorthEast, HexDirection::NorthWest,
+        HexDirection::West, HexDirection::SouthWest, HexDirection::SouthEast
+    ];
+
+    pub fn step(self) -> Hex {
+        match self {
+            HexDirection::East => Hex { q: 1, r: 0 },
+            HexDirection::NorthEast => Hex { q: 1, r: -1 },
+            HexDirection::NorthWest => Hex { q: 0, r: -1 },
+            HexDirection::West => Hex { q: -1, r: 0 },
+            HexDirection::SouthWest => Hex { q: -1, r: 1 },
+            HexDirection::SouthEast => Hex { q: 0, r: 1 }
+        }
+    }
+}
+
+// One or more directions with nothing between them
+#[cfg(feature = "parse")]
+pub fn steps<'a>() -> impl Parser<'a, Vec<HexDirection>> {
+    one_or_more(HexDirection::parser())
+}
+
+impl Hex {
+    pub fn s(&self) -> i64 {
+        -self.q - self.r
+    }
+
+    // Where following `steps` from here leads
+    pub fn walk(self, steps: &[HexDirection]) -> Hex {
+        steps.iter().fold(self, |hex, dir| hex + *dir)
+    }
+
+    // Steps from the origin
+    pub fn length(&self) -> i64 {
+        (self.q.abs() + self.r.abs() + self.s().abs()) / 2
+    }
+
+    pub fn distance(&self, other: Hex) -> i64 {
+        (*self - other).length()
+    }
+
+    // Anticlockwise from east
+    pub fn neighbours(self) -> impl Iterator<Item = Hex> {
+        HexDirection::ALL.iter().map(move |dir| self + *dir)
+    }
+
+    // The 6 * radius hexes at exactly `radius` steps, just this one for a
+    // radius of zero, or none for a negative radius
+    pub fn ring(self, radius: i64) -> Vec<Hex> {
+        if radius < 0 {
+            return Vec::new();
+        }
+        if radius == 0 {
+            return vec![self];
+        }
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+        let mut hex = self + HexDirection::SouthWest.step() * radius;
+        for dir in HexDirection::ALL.iter() {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex += *dir;
+            }
+        }
+        ring
+    }
+
+    // Every hex within `radius` steps, ring by ring outwards from this one
+    pub fn spiral(self, radius: i64) -> Vec<Hex> {
+        (0..=radius).flat_map(|r| self.ring(r)).collect()
+    }
+
+    // The hexes a straight line to `other` passes through, including both ends
+    pub fn line_to(self, other: Hex) -> Vec<Hex> {
+        let n = self.distance(other);
+        if n == 0 {
+            return vec![self];
+        }
+
+        // nudged off the hex edges so points exactly between two hexes round
+        // the same way along the whole line
+        let (q0, r0) = (self.q as f64 + 1e-6, self.r as f64 + 2e-6);
+        let (q1, r1) = (other.q as f64 + 1e-6, other.r as f64 + 2e-6);
+        (0..=n).map(|i| {
+            let t = i as f64 / n as f64;
+            round(q0 + (q1 - q0) * t, r0 + (r1 - r0) * t)
+        }).collect()
+    }
+
+    // Turned anticlockwise about the origin by `sixths` of a full turn, or
+    // clockwise if negative
+    pub fn rotate(self, sixths: i64) -> Hex {
+        (0..sixths.rem_euclid(6)).fold(self, |hex, _| Hex { q: -hex.s(), r: -hex.q })
+    }
+
+    pub fn rotate_about(self, pivot: Hex, sixths: i64) -> Hex {
+        pivot + (self - pivot).rotate(sixths)
+    }
+
+    // Mirrored across the line through the origin where the named coordinate
+    // stays the same, swapping the other two
+    pub fn reflect_q(self) -> Hex {
+        Hex { q: self.q, r: self.s() }
+    }
+
+    pub fn reflect_r(self) -> Hex {
+        Hex { q: self.s(), r: self.r }
+    }
+
+    pub fn reflect_s(self) -> Hex {
+        Hex { q: self.r, r: self.q }
+    }
+
+    pub fn to_offset(self, layout: OffsetLayout) -> OffsetCoord {
+        let Hex { q, r } = self;
+        match layout {
+            OffsetLayout::OddR => OffsetCoord { col: q + (r - (r & 1)) / 2, row: r },
+            OffsetLayout::EvenR => OffsetCoord { col: q + (r + (r & 1)) / 2, row: r },
+            OffsetLayout::OddQ => OffsetCoord { col: q, row: r + (q - (q & 1)) / 2 },
+            OffsetLayout::EvenQ => OffsetCoord { col: q, row: r + (q + (q & 1)) / 2 }
+        }
+    }
+
+    pub fn from_offset(coord: OffsetCoord, layout: OffsetLayout) -> Hex {
+        let OffsetCoord { col, row } = coord;
+        match layout {
+            OffsetLayout::OddR => Hex { q: col - (row - (row & 1)) / 2, r: row },
+            OffsetLayout::EvenR => Hex { q: col - (row + (row & 1)) / 2, r: row },
+            OffsetLayout::OddQ => Hex { q: col, r: row - (col - (col & 1)) / 2 },
+            OffsetLayout::EvenQ => Hex { q: col, r: row - (col + (col & 1)) / 2 }
+        }
+    }
+}
+
+// The hex containing a fractional position, found by rounding each cube
+// coordinate and then fixing up whichever moved furthest
+fn round(q: f64, r: f64) -> Hex {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    Hex { q: rq as i64, r: rr as i64 }
+}
+
+impl From<Hex> for Cube {
+    fn from(hex: Hex) -> Self {
+        Cube { q: hex.q, r: hex.r, s: hex.s() }
+    }
+}
+
+// Fails with the cube itself if its coordinates don't add up to zero
+impl TryFrom<Cube> for Hex {
+    type Error = Cube;
+
+    fn try_from(cube: Cube) -> Result<Self, Cube> {
+        if cube.q + cube.r + cube.s == 0 {
+            Ok(Hex { q: cube.q, r: cube.r })
+        } else {
+            Err(cube)
+        }
+    }
+}
+
+// The six tiles sharing an edge
+impl Position for Hex {
+    type Bounds = HexBounds;
+
+    fn neighbours(self) -> impl Iterator<Item = Hex> {
+        Hex::neighbours(self)
+    }
+}
+
+// The smallest parallelogram of hexes, with sides along the q and r axes,
+// containing a set of hexes. An empty one contains nothing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HexBounds {
+    pub min: Hex,
+    pub max: Hex
+}
+
+impl Default for HexBounds {
+    fn default() -> Self {
+        HexBounds::empty()
+    }
+}
+
+impl HexBounds {
+    pub fn empty() -> Self {
+        HexBounds {
+            min: Hex { q: i64::MAX, r: i64::MAX },
+            max: Hex { q: i64::MIN, r: i64::MIN }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.q > self.max.q || self.min.r > self.max.r
+    }
+}
+
+impl Region<Hex> for HexBounds {
+    fn contains(&self, hex: &Hex) -> bool {
+        self.min.q <= hex.q && hex.q <= self.max.q && self.min.r <= hex.r && hex.r <= self.max.r
+    }
+
+    fn include(&mut self, hex: Hex) {
+        self.min = Hex { q: self.min.q.min(hex.q), r: self.min.r.min(hex.r) };
+        self.max = Hex { q: self.max.q.max(hex.q), r: self.max.r.max(hex.r) };
+    }
+
+    // Row by row, from north to south
+    fn positions(self) -> impl Iterator<Item = Hex> {
+        (self.min.r..=self.max.r).flat_map(move |r|
+            (self.min.q..=self.max.q).map(move |q| Hex { q, r })
+        )
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+    fn add(self, other: Hex) -> Self::Output {
+        Hex {
+            q: self.q + other.q,
+            r: self.r + other.r
+        }
+    }
+}
+
+impl Add<HexDirection> for Hex {
+    type Output = Hex;
+    fn add(self, dir: HexDirection) -> Self::Output {
+        self + dir.step()
+    }
+}
+
+impl AddAssign<HexDirection> for Hex {
+    fn add_assign(&mut self, dir: HexDirection) {
+        *self = *self + dir;
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+    fn sub(self, other: Hex) -> Self::Output {
+        Hex {
+            q: self.q - other.q,
+            r: self.r - other.r
+        }
+    }
+}
+
+impl Mul<i64> for Hex {
+    type Output = Hex;
+    fn mul(self, scale: i64) -> Self::Output {
+        Hex {
+            q: self.q * scale,
+            r: self.r * scale
+        }
+    }
+}
+
+impl Neg for Hex {
+    type Output = Hex;
+    fn neg(self) -> Self::Output {
+        Hex {
+            q: -self.q,
+            r: -self.r
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn coordinate_conversions() {
+        let layouts = [OffsetLayout::OddR, OffsetLayout::EvenR, OffsetLayout::OddQ, OffsetLayout::EvenQ];
+        for hex in (Hex { q: -3, r: 2 }).spiral(4) {
+            assert_eq!(Hex::try_from(Cube::from(hex)), Ok(hex));
+            for &layout in layouts.iter() {
+                assert_eq!(Hex::from_offset(hex.to_offset(layout), layout), hex);
+            }
+        }
+        assert!(Hex::try_from(Cube { q: 1, r: 1, s: 1 }).is_err());
+
+        // in odd-r, odd rows are shoved right, so stepping south-east twice
+        // moves one column
+        let start = Hex::from_offset(OffsetCoord { col: 0, row: 0 }, OffsetLayout::OddR);
+        let hex = start + HexDirection::SouthEast;
+        assert_eq!(hex.to_offset(OffsetLayout::OddR), OffsetCoord { col: 0, row: 1 });
+        assert_eq!((hex + HexDirection::SouthEast).to_offset(OffsetLayout::OddR), OffsetCoord { col: 1, row: 2 });
+    }
+
+    #[test]
+    fn distances_rings_and_spirals() {
+        let centre = Hex { q: 2, r: -1 };
+        assert!(centre.neighbours().all(|n| n.distance(centre) == 1));
+        assert_eq!(Hex::default().walk(&[HexDirection::East; 3]).length(), 3);
+
+        assert_eq!(centre.ring(0), vec![centre]);
+        assert_eq!(centre.ring(-2), vec![]);
+        assert_eq!(centre.spiral(-1), vec![]);
+        for radius in 1..4 {
+            let ring = centre.ring(radius);
+            assert_eq!(ring.len(), 6 * radius as usize);
+            assert!(ring.iter().all(|h| h.distance(centre) == radius));
+        }
+
+        let spiral: HashSet<Hex> = centre.spiral(3).into_iter().collect();
+        assert_eq!(spiral.len(), 37);
+        assert!(spiral.iter().all(|h| h.distance(centre) <= 3));
+    }
+
+    #[test]
+    fn lines() {
+        let a = Hex { q: -2, r: 1 };
+        let b = Hex { q: 3, r: -3 };
+        let line = a.line_to(b);
+        assert_eq!(line.len() as i64, a.distance(b) + 1);
+        assert_eq!((line[0], line[line.len() - 1]), (a, b));
+        assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+        assert_eq!(a.line_to(a), vec![a]);
+    }
+
+    #[test]
+    fn rotations_and_reflections() {
+        let east = HexDirection::East.step();
+        let turns: Vec<Hex> = (0..6).map(|n| east.rotate(n)).collect();
+        let directions: Vec<Hex> = HexDirection::ALL.iter().map(|d| d.step()).collect();
+        assert_eq!(turns, directions);
+        assert_eq!(east.rotate(-1), HexDirection::SouthEast.step());
+
+        let pivot = Hex { q: 1, r: 1 };
+        assert_eq!((pivot + east).rotate_about(pivot, 2), pivot + HexDirection::NorthWest);
+
+        let hex = Hex { q: 3, r: -1 };
+        for reflect in [Hex::reflect_q, Hex::reflect_r, Hex::reflect_s].iter() {
+            assert_eq!(reflect(reflect(hex)), hex);
+            assert_eq!(reflect(hex).length(), hex.length());
+        }
+        assert_eq!(hex.reflect_s(), Hex { q: -1, r: 3 });
+    }
+
+    #[test]
+    #[cfg(feature = "parse")]
+    fn parse_steps() {
+        use HexDirection::*;
+        assert_eq!(Ok(("", vec![East, SouthEast, NorthEast, West])), steps().parse("esenew"));
+        assert_eq!(Hex::default().walk(&steps().parse("nwwswee").unwrap().1), Hex::default());
+        assert!(steps().parse("n").is_err());
+
+        let mut out = String::new();
+        SouthWest.unparse(&mut out);
+        assert_eq!(out, "sw");
+    }
+}
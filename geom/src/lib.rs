@@ -1,26 +1,68 @@
-use std::ops::{Add, Sub, Mul, AddAssign, SubAssign, MulAssign};
+use std::ops::{Add, Sub, Mul, Neg, AddAssign, SubAssign, MulAssign};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+mod grid;
+pub mod hex;
+mod posn;
+mod transform;
+
+pub use grid::{Bounds, DenseGrid, Grid, Position, Region, SparseGrid, Wrapping};
+pub use posn::{BoundsN, Lattice, PosN};
+pub use transform::Transform;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Pos {
     pub x: i64,
     pub y: i64
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Offset {
     pub x: i64,
     pub y: i64
 }
 
-impl Default for Pos {
-    fn default() -> Self {
-        Pos { x: 0, y: 0 }
+// Compass directions on a grid whose y coordinates grow downwards, like the
+// row numbers of a puzzle input
+impl Offset {
+    pub const NORTH: Offset = Offset { x: 0, y: -1 };
+    pub const SOUTH: Offset = Offset { x: 0, y: 1 };
+    pub const EAST: Offset = Offset { x: 1, y: 0 };
+    pub const WEST: Offset = Offset { x: -1, y: 0 };
+    pub const NORTH_EAST: Offset = Offset { x: 1, y: -1 };
+    pub const NORTH_WEST: Offset = Offset { x: -1, y: -1 };
+    pub const SOUTH_EAST: Offset = Offset { x: 1, y: 1 };
+    pub const SOUTH_WEST: Offset = Offset { x: -1, y: 1 };
+
+    // Clockwise from north
+    pub const ORTHOGONAL: [Offset; 4] = [Offset::NORTH, Offset::EAST, Offset::SOUTH, Offset::WEST];
+    pub const ALL_DIRECTIONS: [Offset; 8] = [
+        Offset::NORTH, Offset::NORTH_EAST, Offset::EAST, Offset::SOUTH_EAST,
+        Offset::SOUTH, Offset::SOUTH_WEST, Offset::WEST, Offset::NORTH_WEST
+    ];
+
+    // Steps needed moving only orthogonally
+    pub fn manhattan(&self) -> i64 {
+        self.x.abs() + self.y.abs()
+    }
+
+    // Steps needed moving diagonally as well
+    pub fn chebyshev(&self) -> i64 {
+        self.x.abs().max(self.y.abs())
     }
 }
 
-impl Default for Offset {
-    fn default() -> Self {
-        Offset { x: 0, y: 0 }
+impl Pos {
+    pub fn manhattan(&self, other: Pos) -> i64 {
+        (*self - other).manhattan()
+    }
+
+    pub fn chebyshev(&self, other: Pos) -> i64 {
+        (*self - other).chebyshev()
+    }
+
+    // The eight surrounding positions, clockwise from north
+    pub fn neighbours(self) -> impl Iterator<Item = Pos> {
+        Offset::ALL_DIRECTIONS.iter().map(move |offset| self + *offset)
     }
 }
 
@@ -44,6 +86,30 @@ impl Sub<Offset> for Pos {
     }
 }
 
+impl Sub<Pos> for Pos {
+    type Output = Offset;
+    fn sub(self, other: Pos) -> Self::Output {
+        Offset {
+            x: self.x - other.x,
+            y: self.y - other.y
+        }
+    }
+}
+
+impl AddAssign<Offset> for Pos {
+    fn add_assign(&mut self, offset: Offset) {
+        self.x += offset.x;
+        self.y += offset.y;
+    }
+}
+
+impl SubAssign<Offset> for Pos {
+    fn sub_assign(&mut self, offset: Offset) {
+        self.x -= offset.x;
+        self.y -= offset.y;
+    }
+}
+
 impl Add<Offset> for Offset {
     type Output = Offset;
     fn add(self, offset: Offset) -> Self::Output {
@@ -88,6 +154,16 @@ impl Mul<i64> for Offset {
     }
 }
 
+impl Neg for Offset {
+    type Output = Offset;
+    fn neg(self) -> Self::Output {
+        Offset {
+            x: -self.x,
+            y: -self.y
+        }
+    }
+}
+
 impl MulAssign<i64> for Offset {
     fn mul_assign(&mut self, scale: i64) {
         self.x *= scale;
@@ -97,8 +173,50 @@ impl MulAssign<i64> for Offset {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn positions_and_offsets() {
+        let p = Pos { x: 1, y: 2 };
+        let q = Pos { x: -2, y: 6 };
+        assert_eq!(q - p, Offset { x: -3, y: 4 });
+        assert_eq!(p + (q - p), q);
+        assert_eq!(q + -(q - p), p);
+
+        let mut r = p;
+        r += Offset::SOUTH * 3;
+        r -= Offset::WEST;
+        assert_eq!(r, Pos { x: 2, y: 5 });
+    }
+
+    #[test]
+    fn distances() {
+        let p = Pos { x: 1, y: 2 };
+        let q = Pos { x: -2, y: 6 };
+        assert_eq!(p.manhattan(q), 7);
+        assert_eq!(q.manhattan(p), 7);
+        assert_eq!(p.chebyshev(q), 4);
+        assert_eq!(Offset::NORTH_WEST.manhattan(), 2);
+        assert_eq!(Offset::NORTH_WEST.chebyshev(), 1);
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(Offset::NORTH + Offset::EAST, Offset::NORTH_EAST);
+        assert_eq!(-Offset::NORTH_EAST, Offset::SOUTH_WEST);
+        assert_eq!(Offset::ORTHOGONAL.iter().fold(Offset::default(), |a, b| a + *b), Offset::default());
+        assert!(Offset::ALL_DIRECTIONS.iter().all(|d| d.chebyshev() == 1));
+
+        let neighbours: HashSet<Pos> = Pos::default().neighbours().collect();
+        assert_eq!(neighbours.len(), 8);
+        assert!(neighbours.iter().all(|n| n.chebyshev(Pos::default()) == 1));
+    }
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn positions_order_by_x_then_y() {
+        let mut ps = vec![Pos { x: 1, y: 0 }, Pos { x: 0, y: 1 }, Pos { x: 0, y: 0 }];
+        ps.sort();
+        assert_eq!(ps, vec![Pos { x: 0, y: 0 }, Pos { x: 0, y: 1 }, Pos { x: 1, y: 0 }]);
     }
 }
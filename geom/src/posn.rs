@@ -0,0 +1,265 @@
+use super::{Position, Region};
+use std::iter::FromIterator;
+use std::ops::{Add, Index, IndexMut, Sub};
+
+// A position on an N-dimensional integer lattice, for cellular automata which
+// aren't limited to two dimensions. Axis 0 is x, axis 1 is y, and so on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PosN<const N: usize>(pub [i64; N]);
+
+impl<const N: usize> Default for PosN<N> {
+    fn default() -> Self {
+        PosN([0; N])
+    }
+}
+
+impl<const N: usize> From<[i64; N]> for PosN<N> {
+    fn from(coords: [i64; N]) -> Self {
+        PosN(coords)
+    }
+}
+
+impl<const N: usize> Index<usize> for PosN<N> {
+    type Output = i64;
+    fn index(&self, axis: usize) -> &i64 {
+        &self.0[axis]
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for PosN<N> {
+    fn index_mut(&mut self, axis: usize) -> &mut i64 {
+        &mut self.0[axis]
+    }
+}
+
+impl<const N: usize> Add for PosN<N> {
+    type Output = PosN<N>;
+    fn add(mut self, other: PosN<N>) -> Self::Output {
+        for axis in 0..N {
+            self.0[axis] += other.0[axis];
+        }
+        self
+    }
+}
+
+impl<const N: usize> Sub for PosN<N> {
+    type Output = PosN<N>;
+    fn sub(mut self, other: PosN<N>) -> Self::Output {
+        for axis in 0..N {
+            self.0[axis] -= other.0[axis];
+        }
+        self
+    }
+}
+
+impl<const N: usize> PosN<N> {
+    pub fn manhattan(&self, other: PosN<N>) -> i64 {
+        (*self - other).0.iter().map(|d| d.abs()).sum()
+    }
+
+    pub fn chebyshev(&self, other: PosN<N>) -> i64 {
+        (*self - other).0.iter().map(|d| d.abs()).max().unwrap_or(0)
+    }
+
+    // The 3^N - 1 positions differing by at most one on every axis
+    pub fn moore_neighbours(self) -> impl Iterator<Item = PosN<N>> {
+        (0..3usize.pow(N as u32))
+            .map(move |mut digits| {
+                let mut neighbour = self;
+                for axis in 0..N {
+                    neighbour.0[axis] += (digits % 3) as i64 - 1;
+                    digits /= 3;
+                }
+                neighbour
+            })
+            .filter(move |neighbour| *neighbour != self)
+    }
+
+    // The 2N positions one step along a single axis
+    pub fn von_neumann_neighbours(self) -> impl Iterator<Item = PosN<N>> {
+        (0..N * 2).map(move |i| {
+            let mut neighbour = self;
+            neighbour.0[i / 2] += if i % 2 == 0 { -1 } else { 1 };
+            neighbour
+        })
+    }
+}
+
+// The Moore neighbourhood, so diagonal neighbours count
+impl<const N: usize> Position for PosN<N> {
+    type Bounds = BoundsN<N>;
+
+    fn neighbours(self) -> impl Iterator<Item = PosN<N>> {
+        self.moore_neighbours()
+    }
+}
+
+// The smallest axis-aligned box containing a set of positions, grown by
+// including each of them. An empty box contains nothing, so it can be
+// started before the first position is known.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BoundsN<const N: usize> {
+    pub min: PosN<N>,
+    pub max: PosN<N>
+}
+
+impl<const N: usize> Default for BoundsN<N> {
+    fn default() -> Self {
+        BoundsN::empty()
+    }
+}
+
+impl<const N: usize> BoundsN<N> {
+    pub fn empty() -> Self {
+        BoundsN {
+            min: PosN([i64::MAX; N]),
+            max: PosN([i64::MIN; N])
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..N).any(|axis| self.min[axis] > self.max[axis])
+    }
+
+    pub fn contains(&self, pos: &PosN<N>) -> bool {
+        (0..N).all(|axis| self.min[axis] <= pos[axis] && pos[axis] <= self.max[axis])
+    }
+
+    pub fn include(&mut self, pos: PosN<N>) {
+        for axis in 0..N {
+            self.min[axis] = self.min[axis].min(pos[axis]);
+            self.max[axis] = self.max[axis].max(pos[axis]);
+        }
+    }
+
+    // Larger by `margin` on every side, such as 1 to take in every neighbour
+    // of the positions inside
+    pub fn grow(&self, margin: i64) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut grown = *self;
+        for axis in 0..N {
+            grown.min[axis] -= margin;
+            grown.max[axis] += margin;
+        }
+        grown
+    }
+
+    // Every position in the box, with x changing fastest
+    pub fn lattice(&self) -> Lattice<N> {
+        Lattice {
+            bounds: *self,
+            next: if self.is_empty() { None } else { Some(self.min) }
+        }
+    }
+}
+
+impl<const N: usize> FromIterator<PosN<N>> for BoundsN<N> {
+    fn from_iter<T: IntoIterator<Item = PosN<N>>>(positions: T) -> Self {
+        let mut bounds = BoundsN::empty();
+        bounds.extend(positions);
+        bounds
+    }
+}
+
+impl<const N: usize> Extend<PosN<N>> for BoundsN<N> {
+    fn extend<T: IntoIterator<Item = PosN<N>>>(&mut self, positions: T) {
+        for pos in positions {
+            self.include(pos);
+        }
+    }
+}
+
+impl<const N: usize> Region<PosN<N>> for BoundsN<N> {
+    fn contains(&self, pos: &PosN<N>) -> bool {
+        BoundsN::contains(self, pos)
+    }
+
+    fn include(&mut self, pos: PosN<N>) {
+        BoundsN::include(self, pos)
+    }
+
+    fn positions(self) -> impl Iterator<Item = PosN<N>> {
+        self.lattice()
+    }
+}
+
+pub struct Lattice<const N: usize> {
+    bounds: BoundsN<N>,
+    next: Option<PosN<N>>
+}
+
+impl<const N: usize> Iterator for Lattice<N> {
+    type Item = PosN<N>;
+
+    fn next(&mut self) -> Option<PosN<N>> {
+        let current = self.next?;
+
+        // count up like an odometer, carrying into the next axis
+        let mut next = current;
+        self.next = None;
+        for axis in 0..N {
+            if next[axis] < self.bounds.max[axis] {
+                next[axis] += 1;
+                self.next = Some(next);
+                break;
+            }
+            next[axis] = self.bounds.min[axis];
+        }
+
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn neighbourhoods() {
+        assert_eq!(PosN([0, 0, 0]).moore_neighbours().count(), 26);
+        assert_eq!(PosN([0, 0, 0, 0]).moore_neighbours().count(), 80);
+        assert_eq!(PosN([0; 5]).moore_neighbours().count(), 242);
+
+        let p = PosN([3, -1, 2]);
+        let moore: HashSet<_> = p.moore_neighbours().collect();
+        assert_eq!(moore.len(), 26);
+        assert!(moore.iter().all(|n| n.chebyshev(p) == 1));
+
+        let von_neumann: Vec<_> = p.von_neumann_neighbours().collect();
+        assert_eq!(von_neumann.len(), 6);
+        assert!(von_neumann.iter().all(|n| n.manhattan(p) == 1 && moore.contains(n)));
+    }
+
+    #[test]
+    fn bounds_grow_by_inclusion() {
+        let mut bounds = BoundsN::empty();
+        assert!(bounds.is_empty());
+        assert!(!bounds.contains(&PosN([0, 0])));
+
+        bounds.include(PosN([1, 5]));
+        bounds.include(PosN([-2, 3]));
+        assert_eq!(bounds, BoundsN { min: PosN([-2, 3]), max: PosN([1, 5]) });
+        assert!(bounds.contains(&PosN([0, 4])));
+        assert!(!bounds.contains(&PosN([0, 6])));
+
+        let collected: BoundsN<2> = vec![PosN([1, 5]), PosN([-2, 3])].into_iter().collect();
+        assert_eq!(collected, bounds);
+        assert_eq!(bounds.grow(1), BoundsN { min: PosN([-3, 2]), max: PosN([2, 6]) });
+    }
+
+    #[test]
+    fn lattice_covers_the_box() {
+        let bounds = BoundsN { min: PosN([0, 0, 0]), max: PosN([1, 2, 0]) };
+        let points: Vec<_> = bounds.lattice().collect();
+        assert_eq!(points.len(), 6);
+        assert_eq!(&points[..3], &[PosN([0, 0, 0]), PosN([1, 0, 0]), PosN([0, 1, 0])]);
+        assert!(points.iter().all(|p| bounds.contains(p)));
+
+        assert_eq!(BoundsN::<3>::empty().lattice().count(), 0);
+        assert_eq!(BoundsN::<4>::empty().grow(1).lattice().count(), 0);
+        assert_eq!(PosN([7, 7]).moore_neighbours().collect::<BoundsN<2>>().lattice().count(), 9);
+    }
+}
@@ -0,0 +1,164 @@
+use super::{Offset, Pos};
+
+// The eight symmetries of a square (the dihedral group D4): quarter turns,
+// optionally after mirroring left to right. Turns are anticlockwise as seen
+// on a grid whose y coordinates grow downwards, so a left turn takes east to
+// north.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Transform {
+    left_turns: u8,
+    mirrored: bool
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { left_turns: 0, mirrored: false };
+    pub const ROTATE_LEFT: Transform = Transform { left_turns: 1, mirrored: false };
+    pub const ROTATE_180: Transform = Transform { left_turns: 2, mirrored: false };
+    pub const ROTATE_RIGHT: Transform = Transform { left_turns: 3, mirrored: false };
+
+    // Mirroring left to right, top to bottom, and across each diagonal
+    pub const FLIP_HORIZONTAL: Transform = Transform { left_turns: 0, mirrored: true };
+    pub const FLIP_VERTICAL: Transform = Transform { left_turns: 2, mirrored: true };
+    pub const TRANSPOSE: Transform = Transform { left_turns: 1, mirrored: true };
+    pub const ANTI_TRANSPOSE: Transform = Transform { left_turns: 3, mirrored: true };
+
+    pub fn all() -> impl Iterator<Item = Transform> {
+        [false, true].iter().flat_map(|&mirrored|
+            (0..4).map(move |left_turns| Transform { left_turns, mirrored })
+        )
+    }
+
+    // Any number of quarter turns, with negative numbers turning right
+    pub fn quarter_turns(left_turns: i64) -> Transform {
+        Transform { left_turns: left_turns.rem_euclid(4) as u8, mirrored: false }
+    }
+
+    // An anticlockwise rotation by `degrees`, if it's a multiple of a right angle
+    pub fn rotation(degrees: i64) -> Option<Transform> {
+        if degrees % 90 == 0 {
+            Some(Transform::quarter_turns(degrees / 90))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_reflection(&self) -> bool {
+        self.mirrored
+    }
+
+    // Whether width and height trade places
+    pub fn swaps_axes(&self) -> bool {
+        self.left_turns % 2 == 1
+    }
+
+    // This transform followed by `next`
+    pub fn then(self, next: Transform) -> Transform {
+        // mirroring reverses the direction of any turns made before it
+        let turns = if next.mirrored { -(self.left_turns as i64) } else { self.left_turns as i64 };
+        Transform {
+            left_turns: (turns + next.left_turns as i64).rem_euclid(4) as u8,
+            mirrored: self.mirrored != next.mirrored
+        }
+    }
+
+    pub fn inverse(self) -> Transform {
+        if self.mirrored {
+            self
+        } else {
+            Transform::quarter_turns(-(self.left_turns as i64))
+        }
+    }
+
+    pub fn apply(&self, offset: Offset) -> Offset {
+        let mut result = if self.mirrored { Offset { x: -offset.x, y: offset.y } } else { offset };
+        for _ in 0..self.left_turns {
+            result = Offset { x: result.y, y: -result.x };
+        }
+        result
+    }
+
+    pub fn apply_about(&self, pivot: Pos, pos: Pos) -> Pos {
+        pivot + self.apply(pos - pivot)
+    }
+
+    // The size of a `width` by `height` grid once transformed
+    pub fn grid_size(&self, width: i64, height: i64) -> (i64, i64) {
+        if self.swaps_axes() { (height, width) } else { (width, height) }
+    }
+
+    // Where a cell of a `width` by `height` grid with its top left cell at
+    // the origin ends up when the whole grid is transformed in place
+    pub fn apply_in_grid(&self, pos: Pos, width: i64, height: i64) -> Pos {
+        let corner = |x, y| self.apply(Offset { x, y });
+        let corners = [corner(0, 0), corner(width - 1, 0), corner(0, height - 1), corner(width - 1, height - 1)];
+        let top_left = Offset {
+            x: corners.iter().map(|c| c.x).min().unwrap(),
+            y: corners.iter().map(|c| c.y).min().unwrap()
+        };
+        Pos::default() + (self.apply(pos - Pos::default()) - top_left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn group_laws() {
+        let all: Vec<Transform> = Transform::all().collect();
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 8);
+
+        for &a in &all {
+            assert_eq!(a.then(a.inverse()), Transform::IDENTITY);
+            assert_eq!(a.inverse().then(a), Transform::IDENTITY);
+            assert_eq!(a.then(Transform::IDENTITY), a);
+
+            for &b in &all {
+                let v = Offset { x: 2, y: 7 };
+                assert_eq!(a.then(b).apply(v), b.apply(a.apply(v)));
+                for &c in &all {
+                    assert_eq!(a.then(b).then(c), a.then(b.then(c)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotations_and_reflections() {
+        assert_eq!(Transform::ROTATE_LEFT.apply(Offset::EAST), Offset::NORTH);
+        assert_eq!(Transform::ROTATE_RIGHT.apply(Offset::EAST), Offset::SOUTH);
+        assert_eq!(Transform::ROTATE_180.apply(Offset::NORTH_EAST), Offset::SOUTH_WEST);
+        assert_eq!(Transform::FLIP_HORIZONTAL.apply(Offset::NORTH_EAST), Offset::NORTH_WEST);
+        assert_eq!(Transform::FLIP_VERTICAL.apply(Offset::NORTH_EAST), Offset::SOUTH_EAST);
+        assert_eq!(Transform::TRANSPOSE.apply(Offset { x: 1, y: 2 }), Offset { x: 2, y: 1 });
+        assert_eq!(Transform::ANTI_TRANSPOSE.apply(Offset { x: 1, y: 2 }), Offset { x: -2, y: -1 });
+
+        assert_eq!(Transform::rotation(90), Some(Transform::ROTATE_LEFT));
+        assert_eq!(Transform::rotation(-90), Some(Transform::ROTATE_RIGHT));
+        assert_eq!(Transform::rotation(540), Some(Transform::ROTATE_180));
+        assert_eq!(Transform::rotation(45), None);
+        assert_eq!(Transform::all().filter(Transform::is_reflection).count(), 4);
+    }
+
+    #[test]
+    fn pivots_and_grids() {
+        let pivot = Pos { x: 3, y: 3 };
+        assert_eq!(Transform::ROTATE_LEFT.apply_about(pivot, Pos { x: 5, y: 3 }), Pos { x: 3, y: 1 });
+        assert_eq!(Transform::FLIP_VERTICAL.apply_about(pivot, pivot), pivot);
+
+        // a 3 by 2 grid turned left is 2 by 3, with its top right cell now top left
+        assert_eq!(Transform::ROTATE_LEFT.grid_size(3, 2), (2, 3));
+        assert_eq!(Transform::ROTATE_LEFT.apply_in_grid(Pos { x: 2, y: 0 }, 3, 2), Pos { x: 0, y: 0 });
+        assert_eq!(Transform::ROTATE_LEFT.apply_in_grid(Pos { x: 0, y: 1 }, 3, 2), Pos { x: 1, y: 2 });
+
+        for t in Transform::all() {
+            let (w, h) = t.grid_size(3, 2);
+            let cells: HashSet<Pos> = (0..2).flat_map(|y| (0..3).map(move |x| Pos { x, y }))
+                .map(|p| t.apply_in_grid(p, 3, 2))
+                .collect();
+            assert_eq!(cells.len(), 6);
+            assert!(cells.iter().all(|p| 0 <= p.x && p.x < w && 0 <= p.y && p.y < h));
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, Field, Fields, LitStr, Path, Result};
+
+// Derives `parser::Parse` from `#[parse(...)]` attributes. See the `Parse`
+// trait in the parser crate for the syntax.
+#[proc_macro_derive(Parse, attributes(parse))]
+pub fn derive_parse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+
+    items(&input, item_parser).map(|items| {
+        let (first, rest) = items.split_first().unwrap();
+        quote! {
+            impl ::parser::Parse for #ident {
+                fn parser<'a>() -> ::parser::BoxedParser<'a, Self> {
+                    let parser = #first;
+                    #( let parser = ::parser::Parser::or(parser, #rest); )*
+                    parser
+                }
+            }
+        }
+    }).unwrap_or_else(Error::into_compile_error).into()
+}
+
+// Derives `parser::Unparse` from the same attributes, printing each format
+// with its fields filled in
+#[proc_macro_derive(Unparse, attributes(parse))]
+pub fn derive_unparse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+
+    items(&input, item_printer).map(|arms| quote! {
+        impl ::parser::Unparse for #ident {
+            fn unparse(&self, out: &mut String) {
+                match self {
+                    #( #arms )*
+                }
+            }
+        }
+    }).unwrap_or_else(Error::into_compile_error).into()
+}
+
+// Runs `item` over the struct or each enum variant, in declaration order
+fn items<F>(input: &DeriveInput, item: F) -> Result<Vec<TokenStream2>>
+where
+    F: Fn(TokenStream2, &Fields, Syntax) -> Result<TokenStream2>
+{
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "can't derive for generic types"));
+    }
+
+    match &input.data {
+        Data::Struct(data) => {
+            let syntax = syntax(&input.attrs)?
+                .ok_or_else(|| Error::new_spanned(&input.ident, "expected a #[parse(...)] attribute"))?;
+            Ok(vec![item(quote!(Self), &data.fields, syntax)?])
+        }
+
+        Data::Enum(data) => {
+            if let Some(attr) = input.attrs.iter().find(|a| a.path().is_ident("parse")) {
+                return Err(Error::new_spanned(attr, "#[parse(...)] belongs on each variant of an enum"));
+            }
+            if data.variants.is_empty() {
+                return Err(Error::new_spanned(&input.ident, "can't derive for an empty enum"));
+            }
+
+            let mut variants = vec![];
+            for variant in data.variants.iter() {
+                let syntax = syntax(&variant.attrs)?
+                    .ok_or_else(|| Error::new_spanned(&variant.ident, "expected a #[parse(...)] attribute"))?;
+                let ident = &variant.ident;
+                variants.push(item(quote!(Self::#ident), &variant.fields, syntax)?);
+            }
+            Ok(variants)
+        }
+
+        Data::Union(_) => Err(Error::new_spanned(&input.ident, "can't derive for unions"))
+    }
+}
+
+// --- attributes
+
+enum Syntax {
+    Format(LitStr),
+    With { parse: Path, unparse: Option<Path> }
+}
+
+fn syntax(attrs: &[Attribute]) -> Result<Option<Syntax>> {
+    let mut syntax = None;
+
+    for attr in attrs.iter().filter(|a| a.path().is_ident("parse")) {
+        if syntax.is_some() {
+            return Err(Error::new_spanned(attr, "duplicate #[parse(...)] attribute"));
+        }
+
+        if let Ok(format) = attr.parse_args::<LitStr>() {
+            syntax = Some(Syntax::Format(format));
+            continue;
+        }
+
+        let mut parse = None;
+        let mut unparse = None;
+        attr.parse_nested_meta(|meta| {
+            let function: &mut Option<Path> = if meta.path.is_ident("with") {
+                &mut parse
+            } else if meta.path.is_ident("unparse") {
+                &mut unparse
+            } else {
+                return Err(meta.error("expected a format string, `with = \"function\"` or `unparse = \"function\"`"));
+            };
+            let name: LitStr = meta.value()?.parse()?;
+            *function = Some(name.parse()?);
+            Ok(())
+        })?;
+
+        let parse = parse.ok_or_else(|| Error::new_spanned(attr, "expected `with = \"function\"`"))?;
+        syntax = Some(Syntax::With { parse, unparse });
+    }
+
+    Ok(syntax)
+}
+
+fn field_parser(field: &Field) -> Result<TokenStream2> {
+    let ty = &field.ty;
+    match syntax(&field.attrs)? {
+        None => Ok(quote!(<#ty as ::parser::Parse>::parser())),
+        Some(Syntax::With { parse, .. }) => Ok(quote!(::parser::Parser::boxed(#parse))),
+        Some(Syntax::Format(format)) => {
+            Err(Error::new_spanned(format, "fields take `with = \"function\"`, not a format"))
+        }
+    }
+}
+
+fn field_printer(field: &Field, value: &syn::Ident) -> Result<TokenStream2> {
+    match syntax(&field.attrs)? {
+        None => Ok(quote!(::parser::Unparse::unparse(#value, out);)),
+        Some(Syntax::With { unparse: Some(unparse), .. }) => Ok(quote!(#unparse(#value, out);)),
+        Some(Syntax::With { parse, unparse: None }) => {
+            Err(Error::new_spanned(parse, "fields parsed `with` a function need `unparse = \"function\"` too"))
+        }
+        Some(Syntax::Format(format)) => {
+            Err(Error::new_spanned(format, "fields take `with = \"function\"`, not a format"))
+        }
+    }
+}
+
+// --- formats
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field(FieldRef)
+}
+
+#[derive(Debug, PartialEq)]
+enum FieldRef {
+    Named(String),
+    Index(usize)
+}
+
+// Splits a format into literal text and `{field}` references. `{}` refers to
+// the next positional field, and `{{` and `}}` stand for literal braces.
+fn segments(format: &str) -> std::result::Result<Vec<Segment>, String> {
+    let mut segments = vec![];
+    let mut literal = String::new();
+    let mut next_index = 0;
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err("unterminated `{` in format".to_string())
+                    }
+                }
+
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+
+                let field = if name.is_empty() {
+                    next_index += 1;
+                    FieldRef::Index(next_index - 1)
+                } else if let Ok(index) = name.parse() {
+                    FieldRef::Index(index)
+                } else {
+                    FieldRef::Named(name)
+                };
+                segments.push(Segment::Field(field));
+            }
+
+            '}' => return Err("unmatched `}` in format; use `}}` for a literal brace".to_string()),
+
+            c => literal.push(c)
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+// A format with each field reference resolved to the field's index
+enum Step {
+    Literal(String),
+    Field(usize)
+}
+
+fn resolve(fields: &Fields, format: &LitStr) -> Result<Vec<Step>> {
+    let segments = segments(&format.value()).map_err(|msg| Error::new_spanned(format, msg))?;
+    let positional = fields.iter().all(|f| f.ident.is_none());
+    let mut seen = vec![false; fields.len()];
+    let mut steps = vec![];
+
+    for segment in segments {
+        let index = match segment {
+            Segment::Literal(text) => {
+                steps.push(Step::Literal(text));
+                continue;
+            }
+            Segment::Field(FieldRef::Index(index)) if positional && index < fields.len() => index,
+            Segment::Field(FieldRef::Index(index)) => {
+                return Err(Error::new_spanned(format, format!("no positional field {}", index)));
+            }
+            Segment::Field(FieldRef::Named(name)) => fields.iter()
+                .position(|f| f.ident.as_ref().is_some_and(|i| *i == name))
+                .ok_or_else(|| Error::new_spanned(format, format!("no field named `{}`", name)))?
+        };
+        if std::mem::replace(&mut seen[index], true) {
+            return Err(Error::new_spanned(format, "a field can only appear once in a format"));
+        }
+        steps.push(Step::Field(index));
+    }
+
+    if let Some(index) = seen.iter().position(|seen| !seen) {
+        let field = fields.iter().nth(index).unwrap();
+        let name = field.ident.as_ref().map_or(index.to_string(), |i| i.to_string());
+        return Err(Error::new_spanned(format, format!("field `{}` doesn't appear in the format", name)));
+    }
+    Ok(steps)
+}
+
+// The fields bound to `f0`, `f1`... in a constructor or pattern
+fn bindings(constructor: TokenStream2, fields: &Fields) -> TokenStream2 {
+    let values = fields.iter().enumerate().map(|(index, field)| {
+        let value = format_ident!("f{}", index);
+        match &field.ident {
+            Some(ident) => quote!(#ident: #value),
+            None => quote!(#value)
+        }
+    });
+
+    match fields {
+        Fields::Named(_) => quote!(#constructor { #( #values ),* }),
+        Fields::Unnamed(_) => quote!(#constructor( #( #values ),* )),
+        Fields::Unit => constructor
+    }
+}
+
+fn item_parser(constructor: TokenStream2, fields: &Fields, syntax: Syntax) -> Result<TokenStream2> {
+    let format = match syntax {
+        Syntax::With { parse, .. } => return Ok(quote!(::parser::Parser::boxed(#parse))),
+        Syntax::Format(format) => format
+    };
+
+    let mut parsers = vec![];
+    let mut steps = vec![];
+    for (position, step) in resolve(fields, &format)?.into_iter().enumerate() {
+        let parser = format_ident!("p{}", position);
+        match step {
+            Step::Literal(text) => {
+                parsers.push(quote!(let #parser = ::parser::match_literal(#text);));
+                steps.push(quote!(let (input, _) = ::parser::Parser::parse(&#parser, input)?;));
+            }
+
+            Step::Field(index) => {
+                let value = format_ident!("f{}", index);
+                let field_parser = field_parser(fields.iter().nth(index).unwrap())?;
+                parsers.push(quote!(let #parser = #field_parser;));
+                steps.push(quote!(let (input, #value) = ::parser::Parser::parse(&#parser, input)?;));
+            }
+        }
+    }
+
+    let construct = bindings(constructor, fields);
+    Ok(quote! {
+        {
+            #( #parsers )*
+            ::parser::Parser::boxed(move |input: &'a str| {
+                #( #steps )*
+                Ok((input, #construct))
+            })
+        }
+    })
+}
+
+// A match arm printing one struct or variant
+fn item_printer(constructor: TokenStream2, fields: &Fields, syntax: Syntax) -> Result<TokenStream2> {
+    let format = match syntax {
+        Syntax::With { unparse: Some(unparse), .. } => {
+            let pattern = match fields {
+                Fields::Named(_) => quote!(#constructor { .. }),
+                Fields::Unnamed(_) => quote!(#constructor(..)),
+                Fields::Unit => constructor
+            };
+            return Ok(quote!(#pattern => #unparse(self, out),));
+        }
+        Syntax::With { parse, unparse: None } => {
+            return Err(Error::new_spanned(parse, "deriving Unparse needs `unparse = \"function\"` alongside `with`"));
+        }
+        Syntax::Format(format) => format
+    };
+
+    let mut steps = vec![];
+    for step in resolve(fields, &format)? {
+        match step {
+            Step::Literal(text) => steps.push(quote!(out.push_str(#text);)),
+            Step::Field(index) => {
+                let value = format_ident!("f{}", index);
+                steps.push(field_printer(fields.iter().nth(index).unwrap(), &value)?);
+            }
+        }
+    }
+
+    let pattern = bindings(constructor, fields);
+    Ok(quote! {
+        #pattern => {
+            #( #steps )*
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_fields_and_literals() {
+        assert_eq!(segments("mem[{address}] = {value}"), Ok(vec![
+            Segment::Literal("mem[".to_string()),
+            Segment::Field(FieldRef::Named("address".to_string())),
+            Segment::Literal("] = ".to_string()),
+            Segment::Field(FieldRef::Named("value".to_string()))
+        ]));
+    }
+
+    #[test]
+    fn positional_fields() {
+        assert_eq!(segments("{}-{} {2}"), Ok(vec![
+            Segment::Field(FieldRef::Index(0)),
+            Segment::Literal("-".to_string()),
+            Segment::Field(FieldRef::Index(1)),
+            Segment::Literal(" ".to_string()),
+            Segment::Field(FieldRef::Index(2))
+        ]));
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(segments("{{{0}}}"), Ok(vec![
+            Segment::Literal("{".to_string()),
+            Segment::Field(FieldRef::Index(0)),
+            Segment::Literal("}".to_string())
+        ]));
+    }
+
+    #[test]
+    fn malformed_formats() {
+        assert!(segments("mem[{address").is_err());
+        assert!(segments("a } b").is_err());
+    }
+}
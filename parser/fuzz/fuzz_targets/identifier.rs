@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use parser::*;
+
+// An identifier is a prefix of the input made of letters and hyphens
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok((rest, ident)) = identifier.parse(input) {
+            assert_eq!(format!("{}{}", ident, rest), input);
+            assert!(ident.chars().all(|c| c.is_alphabetic() || c == '-'));
+        }
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use parser::*;
+
+// Whatever integer consumes must be a number std agrees with, and anything
+// too big for an i64 must be reported rather than wrapping
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let digits = input.bytes().take_while(u8::is_ascii_digit).count();
+        match integer.parse(input) {
+            Ok((rest, n)) => {
+                assert_eq!(rest, &input[digits..]);
+                assert_eq!(input[..digits].parse::<i64>(), Ok(n));
+            }
+            Err(_) => assert!(digits == 0 || input[..digits].parse::<i64>().is_err())
+        }
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+use std::borrow::Cow;
+use libfuzzer_sys::fuzz_target;
+use parser::*;
+
+// A quoted string ends at an unescaped closing quote, and its contents are
+// only copied when they contain escapes
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok((rest, s)) = quoted_str('"').parse(input) {
+            let consumed = &input[..input.len() - rest.len()];
+            assert!(consumed.len() >= 2 && consumed.starts_with('"') && consumed.ends_with('"'));
+            match s {
+                Cow::Borrowed(s) => assert_eq!(&consumed[1..consumed.len() - 1], s),
+                Cow::Owned(_) => assert!(consumed.contains('\\'))
+            }
+            assert_eq!(quoted_string().parse(input), Ok((rest, s.into_owned())));
+        }
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use parser::*;
+
+// A word is the longest prefix of letters, split on a character boundary
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok((rest, word)) = word_ref.parse(input) {
+            assert_eq!(format!("{}{}", word, rest), input);
+            assert!(word.chars().all(char::is_alphabetic));
+            assert!(!rest.starts_with(char::is_alphabetic));
+        }
+    }
+});
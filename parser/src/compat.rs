@@ -0,0 +1,219 @@
+// The combinator style of the original day2 and day4 parsers, where a failure
+// is just a tag naming what was expected and the input at that point. The
+// character and number parsers delegate to the main library; `tagged` and
+// `untagged` convert parsers between the two styles.
+//
+// The names clash with the main library, so use one or the other:
+//
+//     use parser::compat::*;
+
+pub type ParseError<'a> = (&'static str, &'a str);
+
+pub type ParseResult<'a, T> = Result<(&'a str, T), ParseError<'a>>;
+
+pub trait Parser<'a, T> {
+    fn parse(&self, input: &'a str) -> ParseResult<'a, T>;
+}
+
+impl<'a, F, T> Parser<'a, T> for F
+where
+    F: Fn(&'a str) -> ParseResult<'a, T>
+{
+    fn parse(&self, input: &'a str) -> ParseResult<'a, T> {
+        self(input)
+    }
+}
+
+// A parser from the main library, failing with `tag` where it failed
+pub fn tagged<'a, P, T>(tag: &'static str, parser: P) -> impl Parser<'a, T>
+where
+    P: crate::Parser<'a, T>
+{
+    move |input| parser.parse(input).map_err(|err| (tag, err.remaining))
+}
+
+// A parser in this style for use with the main library, where its tag
+// becomes the expectation
+pub fn untagged<'a, P, T>(parser: P) -> impl crate::Parser<'a, T>
+where
+    P: Parser<'a, T>
+{
+    move |input| parser.parse(input).map_err(|(tag, rest)| crate::ParseError::expected(rest, tag))
+}
+
+// -- core parser combinators
+
+pub fn map<'a, P, F, A, B>(parser: P, f: F) -> impl Parser<'a, B>
+where
+    P: Parser<'a, A>,
+    F: Fn(A) -> B
+{
+    move |input|
+        parser.parse(input)
+              .map(|(rest, result)| (rest, f(result)))
+}
+
+pub fn seq<'a, P1, P2, R1, R2>(p1: P1, p2: P2) -> impl Parser<'a, (R1, R2)>
+where
+    P1: Parser<'a, R1>,
+    P2: Parser<'a, R2>
+{
+    move |input|
+        p1.parse(input).and_then(|(rest, r1)|
+            p2.parse(rest).map(|(rest2, r2)| (rest2, (r1, r2))))
+}
+
+pub fn first<'a, P1, P2, R1, R2>(p1: P1, p2: P2) -> impl Parser<'a, R1>
+where
+    P1: Parser<'a, R1>,
+    P2: Parser<'a, R2>
+{
+    map(seq(p1, p2), |(r, _)| r)
+}
+
+pub fn second<'a, P1, P2, R1, R2>(p1: P1, p2: P2) -> impl Parser<'a, R2>
+where
+    P1: Parser<'a, R1>,
+    P2: Parser<'a, R2>
+{
+    map(seq(p1, p2), |(_, r)| r)
+}
+
+// Both sides of a separator, unlike `Parser::between` which keeps what's
+// inside two delimiters
+pub fn between<'a, P1, PS, P2, R1, RS, R2>(p1: P1, ps: PS, p2: P2) -> impl Parser<'a, (R1, R2)>
+where
+    P1: Parser<'a, R1>,
+    P2: Parser<'a, R2>,
+    PS: Parser<'a, RS>
+{
+    seq(first(p1, ps), p2)
+}
+
+pub fn or<'a, P1, P2, R>(p1: P1, p2: P2) -> impl Parser<'a, R>
+where
+    P1: Parser<'a, R>,
+    P2: Parser<'a, R>
+{
+    move |input|
+        p1.parse(input).or_else(|_| p2.parse(input))
+}
+
+pub fn one_or_more<'a, P, A>(p: P) -> impl Parser<'a, Vec<A>>
+where
+    P: Parser<'a, A>
+{
+    move |mut input| {
+        let mut result = Vec::new();
+
+        if let Ok((next_input, first_item)) = p.parse(input) {
+            input = next_input;
+            result.push(first_item);
+        } else {
+            return Err(("one or more", input));
+        }
+
+        while let Ok((next_input, next_item)) = p.parse(input) {
+            input = next_input;
+            result.push(next_item);
+        }
+
+        Ok((input, result))
+    }
+}
+
+// ---- parser primitives
+
+pub fn digit(input: &str) -> ParseResult<'_, char> {
+    tagged("digit", crate::digit).parse(input)
+}
+
+pub fn letter(input: &str) -> ParseResult<'_, char> {
+    tagged("letter", crate::letter).parse(input)
+}
+
+pub fn non_whitespace(input: &str) -> ParseResult<'_, char> {
+    tagged("non_whitespace", crate::non_whitespace).parse(input)
+}
+
+pub fn integer(input: &str) -> ParseResult<'_, i64> {
+    tagged("integer", crate::integer).parse(input)
+}
+
+pub fn string<'a>(s: &'static str) -> impl Parser<'a, ()> {
+    tagged("string", crate::match_literal(s))
+}
+
+pub fn whitespace(input: &str) -> ParseResult<'_, ()> {
+    Ok((input.trim_start(), ()))
+}
+
+// ---- error recovery
+
+// Parse one record per line, keeping the errors from lines which fail and
+// carrying on from the next line
+pub fn recover_lines<'a, P, A>(p: P) -> impl Parser<'a, (Vec<A>, Vec<ParseError<'a>>)>
+where
+    P: Parser<'a, A>
+{
+    move |input: &'a str| {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        let mut input = input.trim_start();
+
+        while !input.is_empty() {
+            match p.parse(input) {
+                Ok((rest, record)) => {
+                    records.push(record);
+                    input = rest;
+                }
+                Err(err) => {
+                    errors.push(err);
+                    input = match input.find('\n') {
+                        Some(index) => &input[index + 1..],
+                        None => &input[input.len()..]
+                    };
+                }
+            }
+            input = input.trim_start();
+        }
+
+        Ok((input, (records, errors)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_are_tagged() {
+        assert_eq!(Err(("integer", "x1")), integer("x1"));
+        assert_eq!(Err(("string", "cm")), string("in").parse("cm"));
+        assert_eq!(Err(("one or more", " ab")), one_or_more(letter).parse(" ab"));
+    }
+
+    #[test]
+    fn between_keeps_both_sides() {
+        let item = between(one_or_more(letter), string(":"), one_or_more(non_whitespace));
+        assert_eq!(Ok((" x", (vec!['e', 'c', 'l'], vec!['#', '1']))), item.parse("ecl:#1 x"));
+    }
+
+    #[test]
+    fn converting_between_styles() {
+        use crate::Parser as _;
+        let height = seq(integer, or(string("cm"), string("in")));
+        assert_eq!(
+            Err(crate::ParseError::expected("ft", "string")),
+            untagged(height).parse("12ft")
+        );
+        assert_eq!(Err(("word", "12")), tagged("word", crate::word_ref).parse("12"));
+    }
+
+    #[test]
+    fn recover_lines_keeps_errors() {
+        let (_, (records, errors)) = recover_lines(integer).parse("1\nx\n3").unwrap();
+        assert_eq!(records, vec![1, 3]);
+        assert_eq!(errors, vec![("integer", "x\n3")]);
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use super::Input;
+
+// A parse failure as produced by the combinators. It only knows the input
+// remaining at the point of failure; `locate` turns it into an `Error` with
+// line and column information once the original source is available.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError<'a, I: ?Sized = str> {
+    pub remaining: &'a I,
+    pub expected: Vec<String>,
+    pub context: Vec<String>
+}
+
+impl<'a, I: ?Sized> Clone for ParseError<'a, I> {
+    fn clone(&self) -> Self {
+        ParseError {
+            remaining: self.remaining,
+            expected: self.expected.clone(),
+            context: self.context.clone()
+        }
+    }
+}
+
+impl<'a, I: Input + ?Sized> ParseError<'a, I> {
+    pub fn new(remaining: &'a I) -> Self {
+        ParseError {
+            remaining,
+            expected: vec![],
+            context: vec![]
+        }
+    }
+
+    pub fn expected<S: Into<String>>(remaining: &'a I, what: S) -> Self {
+        ParseError {
+            remaining,
+            expected: vec![what.into()],
+            context: vec![]
+        }
+    }
+
+    // Combine the failures of two alternatives. Whichever got further into the
+    // input is the more useful report; if both stopped at the same place then
+    // either would have been acceptable there.
+    pub fn merge(self, other: ParseError<'a, I>) -> ParseError<'a, I> {
+        use std::cmp::Ordering::*;
+        match self.remaining.len().cmp(&other.remaining.len()) {
+            Less => self,
+            Greater => other,
+            Equal => {
+                let mut merged = self;
+                for e in other.expected {
+                    if !merged.expected.contains(&e) {
+                        merged.expected.push(e);
+                    }
+                }
+                if merged.context.is_empty() {
+                    merged.context = other.context;
+                }
+                merged
+            }
+        }
+    }
+
+    // Labels are pushed as the error bubbles outwards, so the innermost
+    // label comes first.
+    pub fn push_context<S: Into<String>>(mut self, label: S) -> Self {
+        self.context.push(label.into());
+        self
+    }
+
+    pub fn offset(&self, source: &I) -> usize {
+        source.len() - self.remaining.len()
+    }
+
+    pub fn locate(&self, source: &I) -> Error {
+        let offset = self.offset(source);
+        let (line, column) = line_and_column(source, offset);
+
+        Error {
+            offset,
+            line,
+            column,
+            expected: self.expected.clone(),
+            context: self.context.iter().rev().cloned().collect(),
+            found: self.remaining.excerpt()
+        }
+    }
+}
+
+// The line and column of a byte offset into some source text, counting from 1
+pub(crate) fn line_and_column<I: Input + ?Sized>(source: &I, offset: usize) -> (usize, usize) {
+    let consumed = &source.as_bytes()[..offset];
+    let line = consumed.iter().filter(|b| **b == b'\n').count() + 1;
+    let line_start = consumed.iter().rposition(|b| *b == b'\n').map(|i| i + 1).unwrap_or(0);
+    let column = I::width(&consumed[line_start..]) + 1;
+    (line, column)
+}
+
+// A parse failure positioned within its source text. `context` runs from the
+// outermost label to the innermost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub expected: Vec<String>,
+    pub context: Vec<String>,
+    pub found: String
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parse error at line {}, column {}: ", self.line, self.column)?;
+
+        match self.expected.split_last() {
+            None => write!(f, "unexpected input")?,
+            Some((last, [])) => write!(f, "expected {}", last)?,
+            Some((last, init)) => write!(f, "expected {} or {}", init.join(", "), last)?
+        }
+
+        if self.found.is_empty() {
+            write!(f, ", found end of input")?;
+        } else {
+            write!(f, ", found {:?}", self.found)?;
+        }
+
+        if !self.context.is_empty() {
+            write!(f, " while parsing {}", self.context.join(" > "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_prefers_furthest_failure() {
+        let source = "abcdef";
+        let near = ParseError::expected(&source[1..], "`x`");
+        let far = ParseError::expected(&source[3..], "`y`");
+        assert_eq!(near.clone().merge(far.clone()), far);
+        assert_eq!(far.clone().merge(near), far);
+    }
+
+    #[test]
+    fn merge_combines_expectations_at_same_position() {
+        let source = "abc";
+        let a = ParseError::expected(source, "`acc`");
+        let b = ParseError::expected(source, "`jmp`");
+        let c = ParseError::expected(source, "`nop`");
+        assert_eq!(a.merge(b).merge(c).expected, vec!["`acc`", "`jmp`", "`nop`"]);
+    }
+
+    #[test]
+    fn locate_computes_line_and_column() {
+        let source = "acc +1\njmp +4\nxyz -3\n";
+        let error = ParseError::expected(&source[14..], "`acc`").locate(source);
+        assert_eq!(error.offset, 14);
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 1);
+        assert_eq!(error.found, "xyz -3");
+    }
+
+    #[test]
+    fn locate_in_bytes() {
+        let source: &[u8] = b"#..#\n.#x.\n";
+        let error = ParseError::expected(&source[7..], "`#` or `.`").locate(source);
+        assert_eq!((error.offset, error.line, error.column), (7, 2, 3));
+        assert_eq!(error.found, "x.");
+    }
+
+    #[test]
+    fn error_display() {
+        let source = "acc +1\n  xyz -3";
+        let error = ParseError::expected(&source[9..], "`acc`")
+            .merge(ParseError::expected(&source[9..], "`jmp`"))
+            .merge(ParseError::expected(&source[9..], "`nop`"))
+            .push_context("instruction")
+            .push_context("program")
+            .locate(source);
+        assert_eq!(
+            error.to_string(),
+            "parse error at line 2, column 3: expected `acc`, `jmp` or `nop`, found \"xyz -3\" while parsing program > instruction"
+        );
+    }
+}
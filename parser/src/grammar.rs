@@ -0,0 +1,472 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+use super::*;
+
+// A set of rules given as text and interpreted at runtime, for validating
+// inputs whose grammar isn't known until the program runs. Each line defines
+// one rule:
+//
+//     0: 4 1 5
+//     1: "a" | greeting " " name+
+//     name: ("x" | "y")* "z"?
+//
+// Rule names are numbers or words. A rule body is a sequence of literals,
+// quoted and escaped as for `quoted_string`, and rule names, with `|`
+// separating alternatives, `*`, `+` and `?` for repetition, and parentheses
+// for grouping. Rules may be recursive as long as every recursive reference
+// is preceded by something which consumes input.
+//
+// Unlike the combinators, matching explores every alternative and every
+// repetition count, so an ambiguous rule can't commit too early to a choice
+// which a later part of the input rules out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grammar {
+    rules: Rc<HashMap<String, Expr>>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(String),
+    Rule(String),
+    Sequence(Vec<Expr>),
+    Alternative(Vec<Expr>),
+    Repeat(Box<Expr>, usize, Option<usize>)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarError {
+    Syntax(Error),
+    UndefinedRule(String),
+    LeftRecursive(String)
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrammarError::Syntax(err) => write!(f, "{}", err),
+            GrammarError::UndefinedRule(name) => write!(f, "undefined rule `{}`", name),
+            GrammarError::LeftRecursive(name) => write!(f, "rule `{}` is left-recursive", name)
+        }
+    }
+}
+
+impl std::error::Error for GrammarError {}
+
+impl Grammar {
+    pub fn new(text: &str) -> Result<Self, GrammarError> {
+        let mut grammar = Grammar { rules: Rc::new(HashMap::new()) };
+        grammar.extend(text)?;
+        Ok(grammar)
+    }
+
+    // Add more rules, replacing any existing rules with the same names. The
+    // grammar is left unchanged if the result would be invalid.
+    pub fn extend(&mut self, text: &str) -> Result<(), GrammarError> {
+        let (_, parsed) = rules(text).map_err(|e| GrammarError::Syntax(e.locate(text)))?;
+        self.add(parsed)
+    }
+
+    fn add(&mut self, parsed: Vec<(String, Expr)>) -> Result<(), GrammarError> {
+        let mut extended = self.rules.as_ref().clone();
+        extended.extend(parsed);
+        check_references(&extended)?;
+        check_left_recursion(&extended)?;
+        self.rules = Rc::new(extended);
+        Ok(())
+    }
+
+    // A parser for the named rule. It produces the longest text the rule
+    // can match, so it consumes its entire input whenever that's possible.
+    pub fn parser<'a>(&self, name: &str) -> Result<BoxedParser<'a, &'a str>, GrammarError> {
+        if !self.rules.contains_key(name) {
+            return Err(GrammarError::UndefinedRule(name.to_string()));
+        }
+
+        let rules = self.rules.clone();
+        let start = Expr::Rule(name.to_string());
+
+        Ok((move |input: &'a str| {
+            let mut matcher = Matcher { rules: &rules, failure: None };
+            match matcher.matches(&start, input).first() {
+                Some(rest) => Ok((*rest, &input[..input.len() - rest.len()])),
+                None => Err(matcher.failure.unwrap_or_else(|| ParseError::new(input)))
+            }
+        }).boxed())
+    }
+}
+
+// --- matching
+
+struct Matcher<'r, 'a> {
+    rules: &'r HashMap<String, Expr>,
+    failure: Option<ParseError<'a>>
+}
+
+// Remaining inputs after each way of matching, shortest first
+fn distinct(mut remaining: Vec<&str>) -> Vec<&str> {
+    remaining.sort_by_key(|r| r.len());
+    remaining.dedup_by_key(|r| r.len());
+    remaining
+}
+
+impl<'r, 'a> Matcher<'r, 'a> {
+    fn fail(&mut self, err: ParseError<'a>) {
+        self.failure = Some(match self.failure.take() {
+            Some(failure) => failure.merge(err),
+            None => err
+        });
+    }
+
+    fn matches(&mut self, expr: &Expr, input: &'a str) -> Vec<&'a str> {
+        match expr {
+            Expr::Literal(s) => {
+                if input.starts_with(s.as_str()) {
+                    vec![&input[s.len()..]]
+                } else {
+                    self.fail(ParseError::expected(input, format!("`{}`", s)));
+                    vec![]
+                }
+            }
+
+            Expr::Rule(name) => {
+                let rules = self.rules;
+                self.matches(&rules[name], input)
+            }
+
+            Expr::Sequence(exprs) => {
+                let mut remaining = vec![input];
+                for expr in exprs {
+                    let mut next = vec![];
+                    for r in remaining {
+                        next.extend(self.matches(expr, r));
+                    }
+                    remaining = distinct(next);
+                }
+                remaining
+            }
+
+            Expr::Alternative(exprs) => {
+                let mut remaining = vec![];
+                for expr in exprs {
+                    remaining.extend(self.matches(expr, input));
+                }
+                distinct(remaining)
+            }
+
+            Expr::Repeat(expr, min, max) => {
+                let mut results = vec![];
+                let mut current = vec![input];
+                let mut count = 0;
+                loop {
+                    if count >= *min {
+                        results.extend(current.iter().copied());
+                    }
+                    if current.is_empty() || max.is_some_and(|max| count >= max) {
+                        break;
+                    }
+                    // beyond the minimum, repetitions which match nothing can't lead anywhere new
+                    let mut next = vec![];
+                    for r in current {
+                        let matched = self.matches(expr, r);
+                        next.extend(matched.into_iter().filter(|n| count < *min || n.len() < r.len()));
+                    }
+                    current = distinct(next);
+                    count += 1;
+                }
+                distinct(results)
+            }
+        }
+    }
+}
+
+// --- checks
+
+fn references<'e>(expr: &'e Expr, names: &mut Vec<&'e str>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Rule(name) => names.push(name),
+        Expr::Sequence(exprs) | Expr::Alternative(exprs) => {
+            exprs.iter().for_each(|e| references(e, names))
+        }
+        Expr::Repeat(expr, _, _) => references(expr, names)
+    }
+}
+
+fn sorted_names(rules: &HashMap<String, Expr>) -> Vec<&String> {
+    let mut names: Vec<&String> = rules.keys().collect();
+    names.sort();
+    names
+}
+
+fn check_references(rules: &HashMap<String, Expr>) -> Result<(), GrammarError> {
+    for name in sorted_names(rules) {
+        let mut refs = vec![];
+        references(&rules[name], &mut refs);
+        if let Some(undefined) = refs.into_iter().find(|r| !rules.contains_key(*r)) {
+            return Err(GrammarError::UndefinedRule(undefined.to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn nullable(expr: &Expr, nullable_rules: &HashSet<&str>) -> bool {
+    match expr {
+        Expr::Literal(s) => s.is_empty(),
+        Expr::Rule(name) => nullable_rules.contains(name.as_str()),
+        Expr::Sequence(exprs) => exprs.iter().all(|e| nullable(e, nullable_rules)),
+        Expr::Alternative(exprs) => exprs.iter().any(|e| nullable(e, nullable_rules)),
+        Expr::Repeat(expr, min, _) => *min == 0 || nullable(expr, nullable_rules)
+    }
+}
+
+// Rules which could be reached from `expr` before any input is consumed
+fn left_references<'e>(expr: &'e Expr, nullable_rules: &HashSet<&str>, names: &mut Vec<&'e str>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Rule(name) => names.push(name),
+        Expr::Sequence(exprs) => {
+            for e in exprs {
+                left_references(e, nullable_rules, names);
+                if !nullable(e, nullable_rules) {
+                    break;
+                }
+            }
+        }
+        Expr::Alternative(exprs) => {
+            exprs.iter().for_each(|e| left_references(e, nullable_rules, names))
+        }
+        Expr::Repeat(expr, _, _) => left_references(expr, nullable_rules, names)
+    }
+}
+
+fn check_left_recursion(rules: &HashMap<String, Expr>) -> Result<(), GrammarError> {
+    let mut nullable_rules = HashSet::new();
+    loop {
+        let before = nullable_rules.len();
+        for (name, expr) in rules.iter() {
+            if nullable(expr, &nullable_rules) {
+                nullable_rules.insert(name.as_str());
+            }
+        }
+        if nullable_rules.len() == before {
+            break;
+        }
+    }
+
+    for name in sorted_names(rules) {
+        let mut visited = HashSet::new();
+        let mut pending = vec![];
+        left_references(&rules[name], &nullable_rules, &mut pending);
+        while let Some(next) = pending.pop() {
+            if next == name {
+                return Err(GrammarError::LeftRecursive(name.clone()));
+            }
+            if visited.insert(next) {
+                left_references(&rules[next], &nullable_rules, &mut pending);
+            }
+        }
+    }
+    Ok(())
+}
+
+// --- parser
+
+fn inline_space(input: &str) -> ParseResult<'_, ()> {
+    zero_or_more(any_char.pred(|c| *c == ' ' || *c == '\t'))
+        .means(())
+        .parse(input)
+}
+
+fn rule_name(input: &str) -> ParseResult<'_, String> {
+    one_or_more(any_char.pred(|c| c.is_alphanumeric() || *c == '_'))
+        .map(|chars| chars.into_iter().collect())
+        .label("rule name")
+        .parse(input)
+}
+
+fn atom(input: &str) -> ParseResult<'_, Expr> {
+    let literal = quoted_string().map(Expr::Literal);
+    let reference = rule_name.map(Expr::Rule);
+    let group = right(
+        match_literal("("),
+        left(alternatives, right(inline_space, match_literal(")")))
+    );
+
+    one_of3(literal, reference, group).parse(input)
+}
+
+fn term(input: &str) -> ParseResult<'_, Expr> {
+    let (rest, expr) = atom(input)?;
+    let (min, max) = match rest.chars().next() {
+        Some('*') => (0, None),
+        Some('+') => (1, None),
+        Some('?') => (0, Some(1)),
+        _ => return Ok((rest, expr))
+    };
+    Ok((&rest[1..], Expr::Repeat(Box::new(expr), min, max)))
+}
+
+fn sequence(input: &str) -> ParseResult<'_, Expr> {
+    one_or_more(right(inline_space, term))
+        .map(|mut terms| if terms.len() == 1 { terms.remove(0) } else { Expr::Sequence(terms) })
+        .parse(input)
+}
+
+fn alternatives(input: &str) -> ParseResult<'_, Expr> {
+    let alternative = right(right(inline_space, match_literal("|")), sequence);
+
+    pair(sequence, zero_or_more(alternative), |first, mut rest| {
+        if rest.is_empty() {
+            first
+        } else {
+            rest.insert(0, first);
+            Expr::Alternative(rest)
+        }
+    }).parse(input)
+}
+
+fn rule(input: &str) -> ParseResult<'_, (String, Expr)> {
+    tuple2(left(rule_name, match_literal(":")), alternatives)
+        .label("rule")
+        .parse(input)
+}
+
+// Rules embedded in a larger input, up to the first line which isn't a rule
+pub fn grammar<'a>() -> BoxedParser<'a, Grammar> {
+    (|input: &'a str| {
+        let (rest, parsed) = one_or_more(whitespace_wrap(rule)).parse(input)?;
+        let mut grammar = Grammar { rules: Rc::new(HashMap::new()) };
+        match grammar.add(parsed) {
+            Ok(()) => Ok((rest, grammar)),
+            Err(err) => Err(ParseError::expected(input, format!("valid rules ({})", err)))
+        }
+    }).boxed()
+}
+
+fn rules(input: &str) -> ParseResult<'_, Vec<(String, Expr)>> {
+    let (rest, rules) = one_or_more(whitespace_wrap(rule)).parse(input)?;
+    if rest.is_empty() {
+        Ok((rest, rules))
+    } else {
+        Err(ParseError::expected(rest, "rule"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_match(grammar: &Grammar, rule: &str, input: &str) -> bool {
+        matches!(grammar.parser(rule).unwrap().parse(input), Ok(("", _)))
+    }
+
+    #[test]
+    fn literals_sequences_and_alternatives() {
+        let grammar = Grammar::new(
+            "greeting: salutation \" \" name
+             salutation: \"hello\" | \"hi\"
+             name: \"world\" | \"there\""
+        ).unwrap();
+
+        assert_eq!(Ok(("!", "hi there")), grammar.parser("greeting").unwrap().parse("hi there!"));
+        assert!(full_match(&grammar, "greeting", "hello world"));
+        assert!(!full_match(&grammar, "greeting", "hello bob"));
+    }
+
+    #[test]
+    fn repetition_and_groups() {
+        let grammar = Grammar::new("number: \"-\"? (\"1\" | \"2\")+ (\".\" \"0\"*)?").unwrap();
+        assert!(full_match(&grammar, "number", "12"));
+        assert!(full_match(&grammar, "number", "-2.00"));
+        assert!(full_match(&grammar, "number", "1."));
+        assert!(!full_match(&grammar, "number", "-"));
+        assert_eq!(Ok(("3", "21")), grammar.parser("number").unwrap().parse("213"));
+    }
+
+    #[test]
+    fn recursive_rules() {
+        let grammar = Grammar::new("parens: \"(\" parens* \")\"").unwrap();
+        assert!(full_match(&grammar, "parens", "(()(()))"));
+        assert!(!full_match(&grammar, "parens", "(()"));
+    }
+
+    #[test]
+    fn ambiguous_rules_backtrack() {
+        // rule 8 must leave enough 42s for rule 11
+        let grammar = Grammar::new(
+            "0: 8 11
+             8: 42 | 42 8
+             11: 42 31 | 42 11 31
+             42: \"a\"
+             31: \"b\""
+        ).unwrap();
+        assert!(full_match(&grammar, "0", "aaab"));
+        assert!(full_match(&grammar, "0", "aaaabb"));
+        assert!(!full_match(&grammar, "0", "aabb"));
+    }
+
+    #[test]
+    fn extend_replaces_rules() {
+        let mut grammar = Grammar::new("0: 1\n1: \"a\"").unwrap();
+        grammar.extend("1: \"b\"").unwrap();
+        assert!(full_match(&grammar, "0", "b"));
+        assert!(!full_match(&grammar, "0", "a"));
+    }
+
+    #[test]
+    fn match_failure_reports_furthest_expectation() {
+        let grammar = Grammar::new("list: \"[\" (\"x\" | \"y\")* \"]\"").unwrap();
+        assert_eq!(
+            Err(ParseError {
+                remaining: "z]",
+                expected: vec!["`x`".to_string(), "`y`".to_string(), "`]`".to_string()],
+                context: vec![]
+            }),
+            grammar.parser("list").unwrap().parse("[xyz]")
+        );
+    }
+
+    #[test]
+    fn escaped_literals() {
+        let grammar = Grammar::new(r#"quote: "\"" "\u{e9}\\" "\"""#).unwrap();
+        assert!(full_match(&grammar, "quote", "\"é\\\""));
+    }
+
+    #[test]
+    fn undefined_rules_are_rejected() {
+        assert_eq!(Err(GrammarError::UndefinedRule("2".to_string())), Grammar::new("0: 1 2\n1: \"a\""));
+        assert_eq!(
+            Some(GrammarError::UndefinedRule("3".to_string())),
+            Grammar::new("0: \"a\"").unwrap().parser("3").err()
+        );
+    }
+
+    #[test]
+    fn left_recursion_is_rejected() {
+        assert_eq!(Err(GrammarError::LeftRecursive("0".to_string())), Grammar::new("0: 0 \"a\" | \"a\""));
+        assert_eq!(Err(GrammarError::LeftRecursive("a".to_string())), Grammar::new("a: \"x\"* b\nb: a \"y\""));
+    }
+
+    #[test]
+    fn syntax_errors_are_located() {
+        match Grammar::new("0: 1\n1: \"a\" |\n") {
+            Err(GrammarError::Syntax(err)) => assert_eq!((err.line, err.column), (2, 8)),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+
+    #[test]
+    fn grammar_within_larger_input() {
+        let input = "0: 1 1\n1: \"a\"\n\naa\n";
+        let (rest, rules) = grammar().parse(input).unwrap();
+        assert_eq!(rest, "aa\n");
+        assert!(full_match(&rules, "0", "aa"));
+
+        let undefined = grammar().parse("0: 1\n\naa");
+        assert_eq!(
+            undefined.err().map(|e| e.expected),
+            Some(vec!["valid rules (undefined rule `1`)".to_string()])
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use super::*;
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+// A rectangular grid of single-character cells, one row per line, such as a
+// map of trees or seats. Any character other than whitespace is a cell, and
+// the grid ends at a blank line or the end of the input, so grids can be
+// parsed as `paragraphs`. Every row must have as many cells as the first;
+// one which doesn't fails at the column where it goes wrong. A cell type can
+// refuse characters with `TryFrom<char>`, failing at that cell with the
+// conversion error as what was expected.
+pub fn grid<'a, T, I>() -> impl Parser<'a, Vec<Vec<T>>, I>
+where
+    I: Input + ?Sized + 'a,
+    T: TryFrom<char>,
+    T::Error: Display
+{
+    move |input: &'a I| {
+        let (mut input, first_row) = line(row(None)).parse(input)?;
+        let width = first_row.len();
+        let mut rows = vec![first_row];
+
+        while !input.is_empty() && blank_line(input).is_err() {
+            let (next_input, next_row) = line(row(Some(width))).parse(input)?;
+            input = next_input;
+            rows.push(next_row);
+        }
+
+        Ok((input, rows))
+    }
+}
+
+// One or more cells, exactly `width` of them if that's known
+fn row<'a, T, I>(width: Option<usize>) -> impl Parser<'a, Vec<T>, I>
+where
+    I: Input + ?Sized + 'a,
+    T: TryFrom<char>,
+    T::Error: Display
+{
+    let wrong_width = move |input: &'a I|
+        Err(ParseError::expected(input, format!("{} cells in each row", width.unwrap_or(0))));
+
+    move |mut input: &'a I| {
+        let mut cells = Vec::new();
+
+        while let Some(c) = input.first_char().filter(|c| !c.is_whitespace()) {
+            if Some(cells.len()) == width {
+                return wrong_width(input);
+            }
+            match T::try_from(c) {
+                Ok(cell) => cells.push(cell),
+                Err(err) => return Err(ParseError::expected(input, err.to_string()))
+            }
+            input = input.slice_from(input.item_len());
+        }
+
+        match width {
+            Some(width) if cells.len() < width => wrong_width(input),
+            None if cells.is_empty() => Err(ParseError::expected(input, "grid cell")),
+            _ => Ok((input, cells))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Seat {
+        Floor,
+        Empty
+    }
+
+    impl From<char> for Seat {
+        fn from(c: char) -> Self {
+            if c == 'L' { Seat::Empty } else { Seat::Floor }
+        }
+    }
+
+    #[test]
+    fn grid_of_typed_cells() {
+        use Seat::*;
+        assert_eq!(
+            Ok(("", vec![vec![Empty, Floor], vec![Floor, Empty]])),
+            grid().parse("L.\n.L\n")
+        );
+    }
+
+    #[test]
+    fn grid_ends_at_blank_line() {
+        let layers = paragraphs(grid::<char, _>());
+        assert_eq!(
+            Ok(("", vec![vec![vec!['#', '.']], vec![vec!['.', '#'], vec!['#', '#']]])),
+            layers.parse("  #.\r\n\r\n  .#\r\n  ##")
+        );
+    }
+
+    #[test]
+    fn ragged_rows_are_located() {
+        let input = "...\n....\n";
+        let err = grid::<char, _>().parse(input).unwrap_err().locate(input);
+        assert_eq!((err.line, err.column), (2, 4));
+        assert_eq!(err.expected, vec!["3 cells in each row"]);
+
+        let input = "...\n...\n..\n";
+        let err = grid::<char, _>().parse(input).unwrap_err().locate(input);
+        assert_eq!((err.line, err.column), (3, 3));
+    }
+
+    #[test]
+    fn grid_needs_a_cell() {
+        assert_eq!(Err(ParseError::expected("\n", "grid cell")), grid::<char, _>().parse("\n"));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Pixel(bool);
+
+    impl TryFrom<char> for Pixel {
+        type Error = &'static str;
+
+        fn try_from(c: char) -> Result<Self, Self::Error> {
+            match c {
+                '#' => Ok(Pixel(true)),
+                '.' => Ok(Pixel(false)),
+                _ => Err("`#` or `.`")
+            }
+        }
+    }
+
+    #[test]
+    fn cells_can_be_refused() {
+        assert_eq!(Ok(("", vec![vec![Pixel(true), Pixel(false)]])), grid().parse("#.\n"));
+
+        let input = "#.\n.x\n";
+        let err = grid::<Pixel, _>().parse(input).unwrap_err().locate(input);
+        assert_eq!((err.line, err.column), (2, 2));
+        assert_eq!(err.expected, vec!["`#` or `.`"]);
+    }
+
+    #[test]
+    fn grid_of_bytes() {
+        assert_eq!(Ok((&b""[..], vec![vec!['#', '.']])), grid().parse(&b"#.\n"[..]));
+    }
+}
@@ -0,0 +1,97 @@
+// What the combinators need to know about the text they're parsing. Positions
+// are byte offsets, so `str` and `[u8]` inputs share one implementation of
+// everything which only deals with ASCII: literals, digits and whitespace.
+pub trait Input {
+    fn as_bytes(&self) -> &[u8];
+
+    // The input from byte offset `index`, which must be an item boundary
+    fn slice_from(&self, index: usize) -> &Self;
+
+    fn trim_start(&self) -> &Self;
+
+    // The length in bytes of the first item, or 0 at the end of the input
+    fn item_len(&self) -> usize;
+
+    // The first item as a character, treating bytes as Latin-1
+    fn first_char(&self) -> Option<char>;
+
+    // The number of columns occupied by some input, for error positions
+    fn width(bytes: &[u8]) -> usize;
+
+    // The start of the input up to the end of the line, for error messages
+    fn excerpt(&self) -> String;
+
+    fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Input for str {
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+
+    fn slice_from(&self, index: usize) -> &Self {
+        &self[index..]
+    }
+
+    fn trim_start(&self) -> &Self {
+        str::trim_start(self)
+    }
+
+    fn item_len(&self) -> usize {
+        self.chars().next().map_or(0, char::len_utf8)
+    }
+
+    fn first_char(&self) -> Option<char> {
+        self.chars().next()
+    }
+
+    fn width(bytes: &[u8]) -> usize {
+        String::from_utf8_lossy(bytes).chars().count()
+    }
+
+    fn excerpt(&self) -> String {
+        match self.lines().next() {
+            Some(line) if !line.is_empty() => line.chars().take(20).collect(),
+            _ => self.chars().take(1).collect()
+        }
+    }
+}
+
+impl Input for [u8] {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn slice_from(&self, index: usize) -> &Self {
+        &self[index..]
+    }
+
+    fn trim_start(&self) -> &Self {
+        let start = self.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(self.len());
+        &self[start..]
+    }
+
+    fn item_len(&self) -> usize {
+        if self.is_empty() { 0 } else { 1 }
+    }
+
+    fn first_char(&self) -> Option<char> {
+        self.first().map(|b| char::from(*b))
+    }
+
+    fn width(bytes: &[u8]) -> usize {
+        bytes.len()
+    }
+
+    fn excerpt(&self) -> String {
+        let end = self.iter().position(|b| *b == b'\n').unwrap_or(self.len());
+        let line = if end == 0 { &self[..self.item_len()] } else { &self[..end.min(20)] };
+        String::from_utf8_lossy(line).into_owned()
+    }
+}
@@ -0,0 +1,125 @@
+// Properties which should hold for any parser, checked against randomly
+// generated grammars and inputs rather than hand-picked examples.
+
+use proptest::prelude::*;
+use super::*;
+
+// A small grammar built from the core combinators. Every parser it builds
+// consumes at least one character when it succeeds, so repetition always
+// terminates.
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(&'static str),
+    Pair(Box<Expr>, Box<Expr>),
+    Either(Box<Expr>, Box<Expr>),
+    Many(Box<Expr>)
+}
+
+fn expr() -> impl Strategy<Value = Expr> {
+    let literal = prop::sample::select(vec!["a", "b", "ab", "ba", ","]).prop_map(Expr::Literal);
+    literal.prop_recursive(4, 16, 2, |inner| prop_oneof![
+        (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::Pair(Box::new(a), Box::new(b))),
+        (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::Either(Box::new(a), Box::new(b))),
+        inner.prop_map(|e| Expr::Many(Box::new(e)))
+    ])
+}
+
+// Each parser produces the text it matched
+fn build<'a>(expr: &Expr) -> BoxedParser<'a, String> {
+    match expr {
+        Expr::Literal(s) => {
+            let s = *s;
+            match_literal(s).map(move |_| s.to_string())
+        }
+        Expr::Pair(a, b) => pair(build(a), build(b), |a, b| a + &b).boxed(),
+        Expr::Either(a, b) => either(build(a), build(b)).boxed(),
+        Expr::Many(e) => one_or_more(build(e)).map(|matches| matches.concat())
+    }
+}
+
+fn input() -> impl Strategy<Value = String> {
+    "[ab,]{0,12}"
+}
+
+proptest! {
+    #[test]
+    fn parsers_produce_what_they_consume(e in expr(), input in input()) {
+        if let Ok((rest, matched)) = build(&e).parse(&input) {
+            prop_assert_eq!(format!("{}{}", matched, rest), input.clone());
+        }
+    }
+
+    #[test]
+    fn map_identity(e in expr(), input in input()) {
+        prop_assert_eq!(build(&e).map(|x| x).parse(&input), build(&e).parse(&input));
+    }
+
+    #[test]
+    fn map_composition(e in expr(), input in input()) {
+        let composed = build(&e).map(|s| s.len()).map(|n| n * 2);
+        let fused = build(&e).map(|s| s.len() * 2);
+        prop_assert_eq!(composed.parse(&input), fused.parse(&input));
+    }
+
+    #[test]
+    fn either_is_associative(a in expr(), b in expr(), c in expr(), input in input()) {
+        let left_first = either(either(build(&a), build(&b)), build(&c));
+        let right_first = either(build(&a), either(build(&b), build(&c)));
+        prop_assert_eq!(left_first.parse(&input), right_first.parse(&input));
+    }
+
+    // Without a separator after the last item, `sep_by` is an item followed by
+    // any number of separated items; a dangling separator is an error
+    #[test]
+    fn sep_by_is_item_then_separated_items(e in expr(), input in input()) {
+        let separated = build(&e).sep_by(match_literal(","));
+        let reference = pair(build(&e), zero_or_more(right(match_literal(","), build(&e))), |first, mut rest| {
+            rest.insert(0, first);
+            rest
+        });
+
+        match reference.parse(&input) {
+            Ok((rest, _)) if rest.starts_with(',') => prop_assert!(separated.parse(&input).is_err()),
+            result => prop_assert_eq!(separated.parse(&input), result)
+        }
+    }
+
+    #[test]
+    fn sep_by_and_one_or_more_agree_on_terminated_lists(words in prop::collection::vec("[a-zé日ß]{1,4}", 1..6)) {
+        let joined = words.join(",");
+        let terminated = joined.clone() + ",";
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        prop_assert_eq!(word_ref.sep_by(match_literal(",")).parse(&joined), Ok(("", words.clone())));
+        prop_assert_eq!(one_or_more(left(word_ref, match_literal(","))).parse(&terminated), Ok(("", words)));
+        prop_assert!(word_ref.sep_by(match_literal(",")).parse(&terminated).is_err());
+    }
+
+    #[test]
+    fn integer_parses_or_reports_overflow(n in any::<u64>(), suffix in "[^0-9]{0,3}") {
+        let text = format!("{}{}", n, suffix);
+        let expected = if n <= i64::MAX as u64 {
+            Ok((suffix.as_str(), n as i64))
+        } else {
+            Err(ParseError::expected(text.as_str(), "integer in range of i64"))
+        };
+        prop_assert_eq!(integer.parse(text.as_str()), expected);
+    }
+
+    #[test]
+    fn words_and_identifiers_split_on_char_boundaries(input in "\\PC{0,8}") {
+        if let Ok((rest, word)) = word_ref.parse(&input) {
+            prop_assert_eq!(format!("{}{}", word, rest), input.clone());
+            prop_assert!(word.chars().all(char::is_alphabetic));
+        }
+        if let Ok((rest, ident)) = identifier.parse(&input) {
+            prop_assert_eq!(format!("{}{}", ident, rest), input.clone());
+        }
+    }
+
+    #[test]
+    fn quoted_strings_round_trip(s in "\\PC{0,8}", rest in "\\PC{0,4}") {
+        let text = format!("\"{}\"{}", s.replace('\\', "\\\\").replace('"', "\\\""), rest);
+        prop_assert_eq!(quoted_string().parse(&text), Ok((rest.as_str(), s)));
+    }
+}
@@ -1,12 +1,63 @@
 use std::rc::Rc;
 
+pub mod compat;
+mod error;
+mod grammar;
+mod grid;
+mod input;
+#[cfg(test)]
+mod laws;
+mod lines;
+mod memo;
+mod numbers;
+mod parse;
+mod pratt;
+mod print;
+mod quoted;
+mod repeat;
+mod span;
+mod stream;
+mod trace;
+
+pub use error::{Error, ParseError};
+pub use grammar::{grammar, Grammar, GrammarError};
+pub use grid::grid;
+pub use input::Input;
+pub use lines::{blank_line, line, line_end, paragraphs, section};
+pub use memo::{Memoized, MemoStats};
+pub use numbers::*;
+pub use parse::Parse;
+#[cfg(feature = "derive")]
+pub use parser_derive::{Parse, Unparse};
+pub use pratt::{Assoc, Pratt};
+pub use print::{print_lines, print_sep_by, round_trips, unparser, Printer, Unparse};
+pub use quoted::{quoted, quoted_str, quoted_string};
+pub use repeat::{at_least, between_counts, count, many_till, separated_exact};
+pub use span::Spanned;
+pub use stream::{records, Records, StreamError};
+#[cfg(feature = "trace")]
+pub use trace::{finish_trace, record_trace, record_trace_to_file, replay_trace, TraceEvent};
+
+pub type ParseResult<'a, Output, I = str> = Result<(&'a I, Output), ParseError<'a, I>>;
+
+pub trait Parser<'a, Output, I: ?Sized + 'a = str> {
+    fn parse(&self, input: &'a I) -> ParseResult<'a, Output, I>;
+
+    // Parse the whole of `input`, so a grammar which stops part way through
+    // is an error rather than a silently truncated result. Only whitespace
+    // may be left over. A repetition which stops early hides why it stopped,
+    // so for a list of records use `many_till(record, eof())`, which fails
+    // with the record's own error instead.
+    fn parse_complete(&self, input: &'a I) -> Result<Output, Error>
+    where
+        I: Input
+    {
+        self.parse(input)
+            .and_then(|(rest, value)| eof().parse(rest).map(|_| value))
+            .map_err(|err| err.locate(input))
+    }
 
-pub type ParseResult<'a, Output> = Result<(&'a str, Output), &'a str>;
-
-pub trait Parser<'a, Output> {
-    fn parse(&self, input: &'a str) -> ParseResult<'a, Output>;
-
-    fn boxed(self) -> BoxedParser<'a, Output>
+    fn boxed(self) -> BoxedParser<'a, Output, I>
     where
         Self: Sized + 'a,
         Output: 'a
@@ -14,7 +65,7 @@ pub trait Parser<'a, Output> {
         BoxedParser::new(self)
     }
 
-    fn map<F, NewOutput>(self, map_fn: F) -> BoxedParser<'a, NewOutput>
+    fn map<F, NewOutput>(self, map_fn: F) -> BoxedParser<'a, NewOutput, I>
     where
         Self: Sized + 'a,
         Output: 'a,
@@ -24,7 +75,7 @@ pub trait Parser<'a, Output> {
         BoxedParser::new(map(self, map_fn))
     }
 
-    fn means<NewOutput>(self, value: NewOutput) -> BoxedParser<'a, NewOutput>
+    fn means<NewOutput>(self, value: NewOutput) -> BoxedParser<'a, NewOutput, I>
     where
         Self: Sized + 'a,
         Output: 'a,
@@ -33,110 +84,171 @@ pub trait Parser<'a, Output> {
         BoxedParser::new(map(self, move |_| value))
     }
 
-    fn pred<F>(self, pred_fn: F) -> BoxedParser<'a, Output>
+    fn pred<F>(self, pred_fn: F) -> BoxedParser<'a, Output, I>
     where
         Self: Sized + 'a,
         Output: 'a,
+        I: Input,
         F: Fn(&Output) -> bool + 'a
     {
         BoxedParser::new(pred(self, pred_fn))
     }
 
-    fn and_then<F, NextP, NewOutput>(self, f: F) -> BoxedParser<'a, NewOutput>
+    fn and_then<F, NextP, NewOutput>(self, f: F) -> BoxedParser<'a, NewOutput, I>
     where
         Self: Sized + 'a,
         Output: 'a,
         NewOutput: 'a,
-        NextP: Parser<'a, NewOutput> + 'a,
+        NextP: Parser<'a, NewOutput, I> + 'a,
         F: Fn(Output) -> NextP + 'a
     {
         BoxedParser::new(and_then(self, f))
     }
 
-    fn between<PX, PY, RX, RY>(self, before: PX, after: PY) -> BoxedParser<'a, Output>
+    fn between<PX, PY, RX, RY>(self, before: PX, after: PY) -> BoxedParser<'a, Output, I>
     where
         Self: Sized + 'a,
         Output: 'a,
         RX: 'a,
         RY: 'a,
-        PX: Parser<'a, RX> + 'a,
-        PY: Parser<'a, RY> + 'a
+        PX: Parser<'a, RX, I> + 'a,
+        PY: Parser<'a, RY, I> + 'a
     {
         BoxedParser::new(left(right(before, self), after))
     }
 
-    fn sep_by<PS, RS>(self, sep: PS) -> BoxedParser<'a, Vec<Output>>
+    fn sep_by<PS, RS>(self, sep: PS) -> BoxedParser<'a, Vec<Output>, I>
     where
         Self: Sized + 'a,
         Output: 'a,
         RS: 'a,
-        PS: Parser<'a, RS> + 'a
+        PS: Parser<'a, RS, I> + 'a
     {
         BoxedParser::new(sep_by(self, sep))
     }
 
-    fn or<Alternate>(self, alt: Alternate) -> BoxedParser<'a, Output>
+    fn or<Alternate>(self, alt: Alternate) -> BoxedParser<'a, Output, I>
     where
         Self: Sized + 'a,
         Output: 'a,
-        Alternate: Parser<'a, Output> + 'a
+        I: Input,
+        Alternate: Parser<'a, Output, I> + 'a
     {
         BoxedParser::new(either(self, alt))
     }
 
+    fn label<S>(self, name: S) -> BoxedParser<'a, Output, I>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        I: Input,
+        S: Into<String>
+    {
+        let name = name.into();
+        BoxedParser::new(trace::traced(label(self, name.clone()), name))
+    }
+
+    fn context<S>(self, name: S) -> BoxedParser<'a, Output, I>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        I: Input,
+        S: Into<String>
+    {
+        let name = name.into();
+        BoxedParser::new(trace::traced(context(self, name.clone()), name))
+    }
+
+    // Name this parser in traces without changing how it reports errors
+    fn traced<S>(self, name: S) -> BoxedParser<'a, Output, I>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        I: Input,
+        S: Into<String>
+    {
+        BoxedParser::new(trace::traced(self, name.into()))
+    }
+
+    // Wrap the output in its span of the input, to be located later
+    fn spanned(self) -> BoxedParser<'a, Spanned<Output>, I>
+    where
+        Self: Sized + 'a,
+        Output: 'a,
+        I: Input
+    {
+        BoxedParser::new(span::spanned(self))
+    }
+
+    fn memoized(self) -> Memoized<'a, Output, I>
+    where
+        Self: Sized + 'a,
+        Output: Clone + 'a,
+        I: Input
+    {
+        Memoized::new(self)
+    }
 }
 
-#[derive(Clone)]
-pub struct BoxedParser<'a, Output>(Rc<dyn Parser<'a, Output> + 'a>);
+pub struct BoxedParser<'a, Output, I: ?Sized + 'a = str>(Rc<dyn Parser<'a, Output, I> + 'a>);
 
-impl<'a, F, Output> Parser<'a, Output> for F
+impl<'a, F, Output, I> Parser<'a, Output, I> for F
 where
-    F: Fn(&'a str) -> ParseResult<Output>
+    I: ?Sized + 'a,
+    F: Fn(&'a I) -> ParseResult<'a, Output, I>
 {
-    fn parse(&self, input: &'a str) -> ParseResult<'a, Output> {
+    fn parse(&self, input: &'a I) -> ParseResult<'a, Output, I> {
         self(input)
     }
 }
 
-impl<'a, Output> BoxedParser<'a, Output> {
+impl<'a, Output, I: ?Sized + 'a> BoxedParser<'a, Output, I> {
     fn new<P>(parser: P) -> Self
     where
-        P: Parser<'a, Output> + 'a
+        P: Parser<'a, Output, I> + 'a
     {
         BoxedParser(Rc::new(parser))
     }
 }
 
-impl<'a, Output> Parser<'a, Output> for BoxedParser<'a, Output> {
-    fn parse(&self, input: &'a str) -> ParseResult<'a, Output> {
+impl<'a, Output, I: ?Sized + 'a> Clone for BoxedParser<'a, Output, I> {
+    fn clone(&self) -> Self {
+        BoxedParser(self.0.clone())
+    }
+}
+
+impl<'a, Output, I: ?Sized + 'a> Parser<'a, Output, I> for BoxedParser<'a, Output, I> {
+    fn parse(&self, input: &'a I) -> ParseResult<'a, Output, I> {
         self.0.parse(input)
     }
 
-    fn boxed(self) -> BoxedParser<'a, Output> {
+    fn boxed(self) -> BoxedParser<'a, Output, I> {
         self
     }
 }
 
-pub fn match_literal<'a>(expected: &'static str) -> impl Parser<'a, ()> {
-    move |input: &'a str|
-        match input.get(0..expected.len()) {
-            Some(next) if next == expected => {
-                Ok((&input[expected.len()..], ()))
-            }
-            _ => Err(input)
+pub fn match_literal<'a, I>(expected: &'static str) -> impl Parser<'a, (), I>
+where
+    I: Input + ?Sized + 'a
+{
+    move |input: &'a I|
+        if input.as_bytes().starts_with(expected.as_bytes()) {
+            Ok((input.slice_from(expected.len()), ()))
+        } else {
+            Err(ParseError::expected(input, format!("`{}`", expected)))
         }
 }
 
-pub fn identifier(input: &str) -> ParseResult<String> {
+pub fn identifier(input: &str) -> ParseResult<'_, String> {
     let mut matched = String::new();
     let mut chars = input.chars();
 
     match chars.next() {
         Some(next) if next.is_alphabetic() => matched.push(next),
-        _ => return Err(input)
+        _ => return Err(ParseError::expected(input, "identifier"))
     }
 
-    while let Some(next) = chars.next() {
+    for next in chars {
         if next.is_alphabetic() || next == '-' {
             matched.push(next);
         } else {
@@ -148,18 +260,18 @@ pub fn identifier(input: &str) -> ParseResult<String> {
     Ok((&input[next_index..], matched))
 }
 
-pub fn word_ref(input: &str) -> ParseResult<&str> {
+pub fn word_ref(input: &str) -> ParseResult<'_, &str> {
     let mut matched = 0;
     let mut chars = input.chars();
 
     match chars.next() {
-        Some(next) if next.is_alphabetic() => matched += 1,
-        _ => return Err(input)
+        Some(next) if next.is_alphabetic() => matched += next.len_utf8(),
+        _ => return Err(ParseError::expected(input, "word"))
     }
 
-    while let Some(next) = chars.next() {
+    for next in chars {
         if next.is_alphabetic() {
-            matched += 1;
+            matched += next.len_utf8();
         } else {
             break;
         }
@@ -169,10 +281,11 @@ pub fn word_ref(input: &str) -> ParseResult<&str> {
 }
 
 
-pub fn pair<'a, P1, P2, R1, R2, F, R>(parser1: P1, parser2: P2, f: F) -> impl Parser<'a, R>
+pub fn pair<'a, P1, P2, R1, R2, F, R, I>(parser1: P1, parser2: P2, f: F) -> impl Parser<'a, R, I>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>,
+    I: ?Sized + 'a,
+    P1: Parser<'a, R1, I>,
+    P2: Parser<'a, R2, I>,
     F: Fn(R1, R2) -> R
 {
     move |input| {
@@ -183,10 +296,11 @@ where
     }
 }
 
-pub fn tuple2<'a, P1, P2, R1, R2>(parser1: P1, parser2: P2) -> impl Parser<'a, (R1, R2)>
+pub fn tuple2<'a, P1, P2, R1, R2, I>(parser1: P1, parser2: P2) -> impl Parser<'a, (R1, R2), I>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>
+    I: ?Sized + 'a,
+    P1: Parser<'a, R1, I>,
+    P2: Parser<'a, R2, I>
 {
     move |input| {
         parser1.parse(input).and_then(|(next_input, result1)|
@@ -196,11 +310,12 @@ where
     }
 }
 
-pub fn tuple3<'a, P1, P2, P3, R1, R2, R3>(parser1: P1, parser2: P2, parser3: P3) -> impl Parser<'a, (R1, R2, R3)>
+pub fn tuple3<'a, P1, P2, P3, R1, R2, R3, I>(parser1: P1, parser2: P2, parser3: P3) -> impl Parser<'a, (R1, R2, R3), I>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>,
-    P3: Parser<'a, R3>
+    I: ?Sized + 'a,
+    P1: Parser<'a, R1, I>,
+    P2: Parser<'a, R2, I>,
+    P3: Parser<'a, R3, I>
 {
     move |input| {
         parser1.parse(input).and_then(|(next_input, result1)|
@@ -212,25 +327,28 @@ where
     }
 }
 
-pub fn left<'a, P1, P2, R1, R2>(parser1: P1, parser2: P2) -> impl Parser<'a, R1>
+pub fn left<'a, P1, P2, R1, R2, I>(parser1: P1, parser2: P2) -> impl Parser<'a, R1, I>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>
+    I: ?Sized + 'a,
+    P1: Parser<'a, R1, I>,
+    P2: Parser<'a, R2, I>
 {
     pair(parser1, parser2, |left, _| left)
 }
 
-pub fn right<'a, P1, P2, R1, R2>(parser1: P1, parser2: P2) -> impl Parser<'a, R2>
+pub fn right<'a, P1, P2, R1, R2, I>(parser1: P1, parser2: P2) -> impl Parser<'a, R2, I>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>
+    I: ?Sized + 'a,
+    P1: Parser<'a, R1, I>,
+    P2: Parser<'a, R2, I>
 {
     pair(parser1, parser2, |_, right| right)
 }
 
-fn map<'a, P, F, A, B>(parser: P, map_fn: F) -> impl Parser<'a, B>
+fn map<'a, P, F, A, B, I>(parser: P, map_fn: F) -> impl Parser<'a, B, I>
 where
-    P: Parser<'a, A>,
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>,
     F: Fn(A) -> B
 {
     move |input| 
@@ -238,19 +356,17 @@ where
             (next_input, map_fn(result)))
 }
 
-pub fn one_or_more<'a, P, A>(parser: P) -> impl Parser<'a, Vec<A>>
+pub fn one_or_more<'a, P, A, I>(parser: P) -> impl Parser<'a, Vec<A>, I>
 where
-    P: Parser<'a, A>
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>
 {
     move |mut input| {
         let mut result = Vec::new();
 
-        if let Ok((next_input, first_item)) = parser.parse(input) {
-            input = next_input;
-            result.push(first_item);
-        } else {
-            return Err(input);
-        }
+        let (next_input, first_item) = parser.parse(input)?;
+        input = next_input;
+        result.push(first_item);
 
         while let Ok((next_input, next_item)) = parser.parse(input) {
             input = next_input;
@@ -261,9 +377,10 @@ where
     }
 }
 
-pub fn zero_or_more<'a, P, A>(parser: P) -> impl Parser<'a, Vec<A>>
+pub fn zero_or_more<'a, P, A, I>(parser: P) -> impl Parser<'a, Vec<A>, I>
 where
-    P: Parser<'a, A>
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>
 {
     move |mut input| {
         let mut result = Vec::new();
@@ -277,20 +394,18 @@ where
     }
 }
 
-pub fn sep_by<'a, PA, A, PS, S>(parser: PA, sep_parser: PS) -> impl Parser<'a, Vec<A>>
+pub fn sep_by<'a, PA, A, PS, S, I>(parser: PA, sep_parser: PS) -> impl Parser<'a, Vec<A>, I>
 where
-    PA: Parser<'a, A>,
-    PS: Parser<'a, S>
+    I: ?Sized + 'a,
+    PA: Parser<'a, A, I>,
+    PS: Parser<'a, S, I>
 {
     move |mut input| {
         let mut result = Vec::new();
 
-        if let Ok((next_input, first_item)) = parser.parse(input) {
-            input = next_input;
-            result.push(first_item);
-        } else {
-            return Err(input);
-        }
+        let (next_input, first_item) = parser.parse(input)?;
+        input = next_input;
+        result.push(first_item);
 
         loop {
             match sep_parser.parse(input) {
@@ -300,40 +415,172 @@ where
                 }
                 // matching the sep means we must match the next item
                 Ok((next_input, _)) => {
-                    if let Ok((next_input, next_item)) = parser.parse(next_input) {
-                        input = next_input;
-                        result.push(next_item);
-                    } else {
-                        return Err(input);
-                    }
+                    let (next_input, next_item) = parser.parse(next_input)?;
+                    input = next_input;
+                    result.push(next_item);
+                }
+            }
+        }
+    }
+}
+
+// The records which parsed successfully, and the errors from those which didn't
+#[derive(Debug, PartialEq)]
+pub struct Recovered<'a, A, I: ?Sized = str> {
+    pub records: Vec<A>,
+    pub errors: Vec<ParseError<'a, I>>
+}
+
+// Parse whitespace-separated records until the input is exhausted. When a record
+// fails to parse its error is kept and parsing resumes after the next match of
+// `sync`, so one bad record doesn't lose the rest of the input.
+pub fn recover<'a, P, A, PS, S, I>(parser: P, sync: PS) -> impl Parser<'a, Recovered<'a, A, I>, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>,
+    PS: Parser<'a, S, I>
+{
+    move |input: &'a I| {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        let mut input = input.trim_start();
+
+        while !input.is_empty() {
+            match parser.parse(input) {
+                Ok((next_input, record)) => {
+                    records.push(record);
+                    input = next_input;
+                }
+                Err(err) => {
+                    errors.push(err);
+                    input = skip_past(input, &sync);
                 }
             }
+            input = input.trim_start();
         }
+
+        Ok((input, Recovered { records, errors }))
     }
 }
 
-pub fn any_char(input: &str) -> ParseResult<char> {
+pub fn recover_lines<'a, P, A, I>(parser: P) -> impl Parser<'a, Recovered<'a, A, I>, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    recover(parser, match_literal("\n"))
+}
+
+// Skip at least one item, then on to the end of the next match of `sync` or
+// the end of the input
+fn skip_past<'a, PS, S, I>(input: &'a I, sync: &PS) -> &'a I
+where
+    I: Input + ?Sized + 'a,
+    PS: Parser<'a, S, I>
+{
+    if let Ok((next_input, _)) = sync.parse(input) {
+        if next_input.len() < input.len() {
+            return next_input;
+        }
+    }
+
+    let mut rest = input;
+    while !rest.is_empty() {
+        rest = rest.slice_from(rest.item_len());
+        if let Ok((next_input, _)) = sync.parse(rest) {
+            return next_input;
+        }
+    }
+    rest
+}
+
+pub fn any_char(input: &str) -> ParseResult<'_, char> {
     match input.chars().next() {
         Some(next) => Ok((&input[next.len_utf8()..], next)),
-        _ => Err(input)
+        _ => Err(ParseError::expected(input, "any character"))
     }
 }
 
-fn pred<'a, P, A, F>(parser: P, predicate: F) -> impl Parser<'a, A>
+fn char_where<'a, F>(input: &'a str, predicate: F, what: &'static str) -> ParseResult<'a, char>
 where
-    P: Parser<'a, A>,
+    F: Fn(char) -> bool
+{
+    match input.chars().next() {
+        Some(next) if predicate(next) => Ok((&input[next.len_utf8()..], next)),
+        _ => Err(ParseError::expected(input, what))
+    }
+}
+
+pub fn digit(input: &str) -> ParseResult<'_, char> {
+    char_where(input, |c| c.is_ascii_digit(), "digit")
+}
+
+pub fn letter(input: &str) -> ParseResult<'_, char> {
+    char_where(input, char::is_alphabetic, "letter")
+}
+
+pub fn non_whitespace(input: &str) -> ParseResult<'_, char> {
+    char_where(input, |c| !c.is_whitespace(), "non-whitespace character")
+}
+
+pub fn any_byte(input: &[u8]) -> ParseResult<'_, u8, [u8]> {
+    match input.split_first() {
+        Some((next, rest)) => Ok((rest, *next)),
+        _ => Err(ParseError::expected(input, "any byte"))
+    }
+}
+
+pub fn byte<'a>(expected: u8) -> impl Parser<'a, u8, [u8]> {
+    move |input: &'a [u8]|
+        match input.split_first() {
+            Some((next, rest)) if *next == expected => Ok((rest, *next)),
+            _ => Err(ParseError::expected(input, format!("{:?}", expected as char)))
+        }
+}
+
+fn pred<'a, P, A, F, I>(parser: P, predicate: F) -> impl Parser<'a, A, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>,
     F: Fn(&A) -> bool
 {
     move |input| {
-        if let Ok((next_input, value)) = parser.parse(input) {
-            if predicate(&value) {
-                return Ok((next_input, value));
-            }
+        let (next_input, value) = parser.parse(input)?;
+        if predicate(&value) {
+            Ok((next_input, value))
+        } else {
+            Err(ParseError::new(input))
         }
-        Err(input)
     }
 }
 
+// A parser which fails without consuming anything is reported as expecting
+// `name`; one which fails part way through keeps its own error and gains
+// `name` as context.
+fn label<'a, P, A, I>(parser: P, name: String) -> impl Parser<'a, A, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    move |input: &'a I|
+        parser.parse(input).map_err(|err|
+            if err.remaining.len() == input.len() {
+                ParseError::expected(err.remaining, name.clone())
+            } else {
+                err.push_context(name.clone())
+            }
+        )
+}
+
+fn context<'a, P, A, I>(parser: P, name: String) -> impl Parser<'a, A, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    move |input|
+        parser.parse(input).map_err(|err| err.push_context(name.clone()))
+}
+
 pub fn whitespace_char<'a>() -> impl Parser<'a, char> {
     pred(any_char, |c| c.is_whitespace())
 }
@@ -346,52 +593,45 @@ pub fn space0<'a>() -> impl Parser<'a, Vec<char>> {
     zero_or_more(whitespace_char())
 }
 
-pub fn quoted_string<'a>() -> impl Parser<'a, String> {
-    right(
-        match_literal("\""),
-        left(
-            zero_or_more(any_char.pred(|c| *c != '"')),
-            match_literal("\"")
-        )
-    )
-    .map(|chars| chars.into_iter().collect())
-}
-
-pub fn either<'a, P1, P2, A>(parser1: P1, parser2: P2) -> impl Parser<'a, A>
+pub fn either<'a, P1, P2, A, I>(parser1: P1, parser2: P2) -> impl Parser<'a, A, I>
 where
-    P1: Parser<'a, A>,
-    P2: Parser<'a, A>
+    I: Input + ?Sized + 'a,
+    P1: Parser<'a, A, I>,
+    P2: Parser<'a, A, I>
 {
     move |input|
         match parser1.parse(input) {
             ok@Ok(_) => ok,
-            Err(_) => parser2.parse(input)
+            Err(err1) => parser2.parse(input).map_err(|err2| err1.merge(err2))
         }
 }
 
-pub fn one_of3<'a, P1, P2, P3, A>(p1: P1, p2: P2, p3: P3) -> impl Parser<'a, A>
+pub fn one_of3<'a, P1, P2, P3, A, I>(p1: P1, p2: P2, p3: P3) -> impl Parser<'a, A, I>
 where
-    P1: Parser<'a, A>,
-    P2: Parser<'a, A>,
-    P3: Parser<'a, A>
+    I: Input + ?Sized + 'a,
+    P1: Parser<'a, A, I>,
+    P2: Parser<'a, A, I>,
+    P3: Parser<'a, A, I>
 {
     either(either(p1, p2), p3)
 }
 
-pub fn one_of4<'a, P1, P2, P3, P4, A>(p1: P1, p2: P2, p3: P3, p4: P4) -> impl Parser<'a, A>
+pub fn one_of4<'a, P1, P2, P3, P4, A, I>(p1: P1, p2: P2, p3: P3, p4: P4) -> impl Parser<'a, A, I>
 where
-    P1: Parser<'a, A>,
-    P2: Parser<'a, A>,
-    P3: Parser<'a, A>,
-    P4: Parser<'a, A>
+    I: Input + ?Sized + 'a,
+    P1: Parser<'a, A, I>,
+    P2: Parser<'a, A, I>,
+    P3: Parser<'a, A, I>,
+    P4: Parser<'a, A, I>
 {
     either(either(p1, p2), either(p3, p4))
 }
 
-pub fn and_then<'a, P, F, A, B, NextP>(parser: P, f: F) -> impl Parser<'a, B>
+pub fn and_then<'a, P, F, A, B, NextP, I>(parser: P, f: F) -> impl Parser<'a, B, I>
 where
-    P: Parser<'a, A>,
-    NextP: Parser<'a, B>,
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>,
+    NextP: Parser<'a, B, I>,
     F: Fn(A) -> NextP
 {
     move |input|
@@ -401,27 +641,28 @@ where
         }
 }
 
-pub fn whitespace_wrap<'a, P, A>(parser: P) -> impl Parser<'a, A>
+pub fn whitespace_wrap<'a, P, A, I>(parser: P) -> impl Parser<'a, A, I>
 where
-    P: Parser<'a, A>
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
 {
-    right(space0(), left(parser, space0()))
+    move |input: &'a I|
+        parser.parse(input.trim_start())
+            .map(|(next_input, result)| (next_input.trim_start(), result))
 }
 
-
-pub fn integer(input: &str) -> ParseResult<i64> {
-    let digit_as_num = any_char.pred(|c| c.is_digit(10)).map(|d| (d as i64) - 48);
-
-    if let Ok((rest, first_digit)) = digit_as_num.parse(input) {
-        let mut i = first_digit;
-        let mut remainder = rest;
-        while let Ok((rest, next_digit)) = digit_as_num.parse(remainder) {
-            i = i * 10 + next_digit;
-            remainder = rest;
+// The end of the input, after any trailing whitespace
+pub fn eof<'a, I>() -> impl Parser<'a, (), I>
+where
+    I: Input + ?Sized + 'a
+{
+    move |input: &'a I| {
+        let rest = input.trim_start();
+        if rest.is_empty() {
+            Ok((rest, ()))
+        } else {
+            Err(ParseError::expected(rest, "end of input"))
         }
-        Ok((remainder, i))
-    } else {
-        Err(input)
     }
 }
 
@@ -451,7 +692,7 @@ mod tests {
     fn literal_parser_fails_on_no_match() {
         let parse_joe = match_literal("Hello Joe!");
         assert_eq!(
-            Err("Hello Mike!"),
+            Err(ParseError::expected("Hello Mike!", "`Hello Joe!`")),
             parse_joe.parse("Hello Mike!")
         );
     }
@@ -475,11 +716,26 @@ mod tests {
     #[test]
     fn identifier_parser_fails_on_non_alphabetic_character() {
         assert_eq!(
-            Err("!not at all an identifier"),
+            Err(ParseError::expected("!not at all an identifier", "identifier")),
             identifier.parse("!not at all an identifier")
         );
     }
 
+    #[test]
+    fn word_parser_handles_multibyte_characters() {
+        assert_eq!(Ok((" ç", "naïve")), word_ref.parse("naïve ç"));
+        assert_eq!(Ok(("", "日本")), word_ref.parse("日本"));
+    }
+
+    #[test]
+    fn character_class_parsers() {
+        assert_eq!(Ok(("a", '7')), digit.parse("7a"));
+        assert_eq!(Err(ParseError::expected("a7", "digit")), digit.parse("a7"));
+        assert_eq!(Ok(("", 'é')), letter.parse("é"));
+        assert_eq!(Ok(("x", '#')), non_whitespace.parse("#x"));
+        assert_eq!(Err(ParseError::expected(" x", "non-whitespace character")), non_whitespace.parse(" x"));
+    }
+
     #[test]
     fn means_combinator() {
         let parser = match_literal("foo").means("bar");
@@ -493,16 +749,16 @@ mod tests {
             Ok(("/>", "my-first-element".to_string())),
             tag_opener.parse("<my-first-element/>")
         );
-        assert_eq!(Err("oops"), tag_opener.parse("oops"));
-        assert_eq!(Err("!oops"), tag_opener.parse("<!oops"));
+        assert_eq!(Err(ParseError::expected("oops", "`<`")), tag_opener.parse("oops"));
+        assert_eq!(Err(ParseError::expected("!oops", "identifier")), tag_opener.parse("<!oops"));
     }
 
     #[test]
     fn one_or_more_combinator() {
         let parser = one_or_more(match_literal("ha"));
         assert_eq!(Ok(("", vec![(), (), ()])), parser.parse("hahaha"));
-        assert_eq!(Err("ahah"), parser.parse("ahah"));
-        assert_eq!(Err(""), parser.parse(""));
+        assert_eq!(Err(ParseError::expected("ahah", "`ha`")), parser.parse("ahah"));
+        assert_eq!(Err(ParseError::expected("", "`ha`")), parser.parse(""));
     }
 
     #[test]
@@ -517,7 +773,7 @@ mod tests {
     fn predicate_combinator() {
         let parser = pred(any_char, |c| *c == 'o');
         assert_eq!(Ok(("mg", 'o')), parser.parse("omg"));
-        assert_eq!(Err("lol"), parser.parse("lol"));
+        assert_eq!(Err(ParseError::new("lol")), parser.parse("lol"));
     }
 
     #[test]
@@ -526,6 +782,144 @@ mod tests {
         assert_eq!(Ok(("", vec![1,2,3,4])), parser.parse("1,2,3,4"));
     }
 
+    #[test]
+    fn sep_by_reports_failed_item() {
+        let parser = integer.sep_by(match_literal(","));
+        assert_eq!(Err(ParseError::expected("x", "integer")), parser.parse("1,2,x"));
+    }
+
+    // A separator commits to another item, so a trailing one is an error
+    // rather than being left unparsed
+    #[test]
+    fn sep_by_fails_on_trailing_separator() {
+        let parser = integer.sep_by(match_literal(","));
+        assert_eq!(Err(ParseError::expected("", "integer")), parser.parse("1,2,"));
+        assert_eq!(Err(ParseError::expected(" 3", "integer")), parser.parse("1,2, 3"));
+    }
+
+    #[test]
+    fn either_collects_expected_alternatives() {
+        let parser = one_of3(match_literal("acc"), match_literal("jmp"), match_literal("nop"));
+        let source = "xyz +1";
+        let error = parser.parse(source).unwrap_err();
+        assert_eq!(error.expected, vec!["`acc`", "`jmp`", "`nop`"]);
+        assert_eq!(
+            error.locate(source).to_string(),
+            "parse error at line 1, column 1: expected `acc`, `jmp` or `nop`, found \"xyz +1\""
+        );
+    }
+
+    #[test]
+    fn either_reports_furthest_failure() {
+        let parser = right(match_literal("mem["), integer).or(match_literal("mask").means(0));
+        assert_eq!(Err(ParseError::expected("x]", "integer")), parser.parse("mem[x]"));
+    }
+
+    #[test]
+    fn label_replaces_expectations_at_start() {
+        let parser = one_of3(match_literal("acc"), match_literal("jmp"), match_literal("nop"))
+            .label("instruction");
+        assert_eq!(Err(ParseError::expected("xyz", "instruction")), parser.parse("xyz"));
+    }
+
+    #[test]
+    fn label_adds_context_after_partial_match() {
+        let parser = right(match_literal("mem["), integer).label("write");
+        assert_eq!(
+            Err(ParseError::expected("x]", "integer").push_context("write")),
+            parser.parse("mem[x]")
+        );
+    }
+
+    #[test]
+    fn context_is_stacked() {
+        let header = integer.between(match_literal("Tile "), match_literal(":")).context("tile header");
+        let tile = left(header, match_literal("\n")).context("tile");
+        let source = "Tile 2311;\n";
+        let error = tile.parse(source).unwrap_err().locate(source);
+        assert_eq!(error.context, vec!["tile", "tile header"]);
+        assert_eq!(
+            error.to_string(),
+            "parse error at line 1, column 10: expected `:`, found \";\" while parsing tile > tile header"
+        );
+    }
+
+    #[test]
+    fn parse_complete_rejects_leftover_input() {
+        let numbers = one_or_more(whitespace_wrap(integer));
+        assert_eq!(numbers.parse_complete("1 2\n3\n\n"), Ok(vec![1, 2, 3]));
+        assert_eq!(eof().parse(&b" \r\n"[..]), Ok((&b""[..], ())));
+
+        let error = numbers.parse_complete("1 2\nthree\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.expected, vec!["end of input"]);
+    }
+
+    #[test]
+    fn parse_complete_reports_why_records_stopped() {
+        let records = many_till(whitespace_wrap(left(integer, match_literal(";"))), eof());
+        assert_eq!(records.parse_complete("1;\n2;\n"), Ok(vec![1, 2]));
+
+        let error = records.parse_complete("1;\n2;\n3x\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 2));
+        assert_eq!(error.expected, vec!["`;`"]);
+    }
+
+    #[test]
+    fn recover_lines_skips_bad_records() {
+        let parser = recover_lines(integer.sep_by(match_literal(",")));
+        let (rest, recovered) = parser.parse("1,2\n3,x,4\n\n5\n6,\n").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(recovered.records, vec![vec![1, 2], vec![5]]);
+        assert_eq!(recovered.errors, vec![
+            ParseError::expected("x,4\n\n5\n6,\n", "integer"),
+            ParseError::expected("\n", "integer")
+        ]);
+    }
+
+    #[test]
+    fn recover_resumes_at_sync_point() {
+        let parser = recover(integer, match_literal(";"));
+        let (_, recovered) = parser.parse("1 2 x 3 ; 4").unwrap();
+        assert_eq!(recovered.records, vec![1, 2, 4]);
+        assert_eq!(recovered.errors.len(), 1);
+    }
+
+    #[test]
+    fn combinators_over_bytes() {
+        let input: &[u8] = b"mem[8] = 11\nmem[7] = 101\n";
+        let write = pair(
+            right(match_literal("mem["), integer),
+            right(match_literal("] = "), integer),
+            |address, value| (address, value)
+        );
+        let parser = one_or_more(whitespace_wrap(write));
+        assert_eq!(Ok((&b""[..], vec![(8, 11), (7, 101)])), parser.parse(input));
+    }
+
+    #[test]
+    fn sep_by_over_bytes() {
+        let parser = integer.sep_by(byte(b','));
+        assert_eq!(Ok((&b";"[..], vec![7, 13, 59])), parser.parse(&b"7,13,59;"[..]));
+        assert_eq!(Err(ParseError::expected(&b"x"[..], "integer")), parser.parse(&b"7,x"[..]));
+    }
+
+    #[test]
+    fn byte_grid_rows() {
+        let cell = any_byte.pred(|b| *b == b'#' || *b == b'.');
+        let rows = one_or_more(whitespace_wrap(one_or_more(cell)));
+        let (_, grid) = rows.parse(&b"#..#\n.##.\n"[..]).unwrap();
+        assert_eq!(grid, vec![b"#..#".to_vec(), b".##.".to_vec()]);
+    }
+
+    #[test]
+    fn recover_lines_over_bytes() {
+        let parser = recover_lines(integer);
+        let (_, recovered) = parser.parse(&b"1\nx\n3\n"[..]).unwrap();
+        assert_eq!(recovered.records, vec![1, 3]);
+        assert_eq!(recovered.errors, vec![ParseError::expected(&b"x\n3\n"[..], "integer")]);
+    }
+
     #[test]
     fn quoted_string_parser() {
         assert_eq!(
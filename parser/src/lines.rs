@@ -0,0 +1,173 @@
+use super::*;
+
+// Combinators for line-structured input. Lines may end with "\n" or "\r\n",
+// and paragraphs are separated by one or more blank lines, so inputs read
+// from Windows files or with extra blank lines at the end parse the same way.
+
+pub fn line_end<'a, I>(input: &'a I) -> ParseResult<'a, (), I>
+where
+    I: Input + ?Sized + 'a
+{
+    let bytes = input.as_bytes();
+    if bytes.starts_with(b"\r\n") {
+        Ok((input.slice_from(2), ()))
+    } else if bytes.starts_with(b"\n") {
+        Ok((input.slice_from(1), ()))
+    } else {
+        Err(ParseError::expected(input, "end of line"))
+    }
+}
+
+fn inline_space_len<I: Input + ?Sized>(input: &I) -> usize {
+    input.as_bytes().iter().take_while(|b| **b == b' ' || **b == b'\t').count()
+}
+
+// A line containing nothing but spaces and tabs
+pub fn blank_line<'a, I>(input: &'a I) -> ParseResult<'a, (), I>
+where
+    I: Input + ?Sized + 'a
+{
+    line_end(input.slice_from(inline_space_len(input)))
+        .map_err(|_| ParseError::expected(input, "blank line"))
+}
+
+// `parser` followed by the end of its line, or of the input. Spaces and tabs
+// are skipped on either side, so indented input parses the same way.
+pub fn line<'a, P, A, I>(parser: P) -> impl Parser<'a, A, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    move |input: &'a I| {
+        let (rest, value) = parser.parse(input.slice_from(inline_space_len(input)))?;
+        let rest = rest.slice_from(inline_space_len(rest));
+        if rest.is_empty() {
+            Ok((rest, value))
+        } else {
+            line_end(rest).map(|(rest, _)| (rest, value))
+        }
+    }
+}
+
+// Having parsed a paragraph from `input` up to `rest`, skip the blank lines
+// which end it. The paragraph's parser may or may not have consumed line ends
+// itself, so whitespace on both sides of `rest` counts towards them.
+fn end_of_paragraph<'a, I>(input: &'a I, rest: &'a I) -> Result<&'a I, ParseError<'a, I>>
+where
+    I: Input + ?Sized + 'a
+{
+    let consumed = &input.as_bytes()[..input.len() - rest.len()];
+    let newlines_before = consumed.iter().rev()
+        .take_while(|b| b.is_ascii_whitespace())
+        .filter(|b| **b == b'\n')
+        .count();
+
+    let space = rest.as_bytes().iter().take_while(|b| b.is_ascii_whitespace()).count();
+    let newlines_after = rest.as_bytes()[..space].iter().filter(|b| **b == b'\n').count();
+
+    let next = rest.slice_from(space);
+    if next.is_empty() || newlines_before + newlines_after >= 2 {
+        Ok(next)
+    } else {
+        Err(ParseError::expected(rest, "blank line"))
+    }
+}
+
+// One or more paragraphs making up the rest of the input, each parsed by
+// `parser`. Blank lines before the first paragraph and after the last are
+// ignored.
+pub fn paragraphs<'a, P, A, I>(parser: P) -> impl Parser<'a, Vec<A>, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    move |mut input: &'a I| {
+        while let Ok((next_input, _)) = blank_line(input) {
+            input = next_input;
+        }
+
+        let mut result = Vec::new();
+        loop {
+            let (next_input, item) = parser.parse(input)?;
+            result.push(item);
+            input = end_of_paragraph(input, next_input)?;
+            if input.is_empty() {
+                return Ok((input, result));
+            }
+        }
+    }
+}
+
+// A paragraph starting with a header line, such as "your ticket:". The
+// section ends at a blank line or the end of the input, and only the body's
+// value is kept.
+pub fn section<'a, PH, H, PB, B, I>(header: PH, body: PB) -> impl Parser<'a, B, I>
+where
+    I: Input + ?Sized + 'a,
+    PH: Parser<'a, H, I>,
+    PB: Parser<'a, B, I>
+{
+    let header = line(header);
+    move |input: &'a I| {
+        let (next_input, _) = header.parse(input)?;
+        let (next_input, value) = body.parse(next_input)?;
+        Ok((end_of_paragraph(input, next_input)?, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters<'a>() -> impl Parser<'a, String> {
+        one_or_more(letter).map(|cs| cs.into_iter().collect())
+    }
+
+    #[test]
+    fn line_ends() {
+        assert_eq!(Ok(("x", ())), line_end("\r\nx"));
+        assert_eq!(Ok(("x", ())), blank_line(" \t\nx"));
+        assert_eq!(Err(ParseError::expected(" x\n", "blank line")), blank_line(" x\n"));
+        assert_eq!(Ok(("next", "abc".to_string())), line(letters()).parse("\tabc  \r\nnext"));
+        assert_eq!(Ok(("", "abc".to_string())), line(letters()).parse("abc"));
+        assert_eq!(Err(ParseError::expected("1\n", "end of line")), line(letters()).parse("abc1\n"));
+    }
+
+    #[test]
+    fn paragraphs_of_lines() {
+        let groups = paragraphs(one_or_more(line(letters())));
+        let expected = vec![vec!["ab".to_string(), "c".to_string()], vec!["d".to_string()]];
+        assert_eq!(Ok(("", expected.clone())), groups.parse("ab\nc\n\nd\n"));
+        assert_eq!(Ok(("", expected.clone())), groups.parse("\n\nab\r\nc\r\n\r\n\r\n  \nd\r\n\r\n"));
+        assert_eq!(Ok(("", expected)), groups.parse("ab\nc\n\nd"));
+    }
+
+    #[test]
+    fn paragraphs_which_consume_trailing_whitespace() {
+        let words = paragraphs(whitespace_wrap(letters()));
+        assert_eq!(Ok(("", vec!["a".to_string(), "b".to_string()])), words.parse("a\n\n b \n"));
+    }
+
+    #[test]
+    fn paragraphs_must_be_separated_by_blank_lines() {
+        let groups = paragraphs(letters());
+        assert_eq!(Err(ParseError::expected("\nb", "blank line")), groups.parse("a\nb"));
+        assert_eq!(Err(ParseError::expected("1", "letter")), groups.parse("a\n\n1"));
+    }
+
+    #[test]
+    fn sections() {
+        let input = "first:\n1,2\n\nsecond: \r\n3\r\n4\r\n";
+        let numbers = tuple2(
+            section(match_literal("first:"), integer.sep_by(match_literal(","))),
+            section(match_literal("second:"), one_or_more(line(integer)))
+        );
+        assert_eq!(Ok(("", (vec![1, 2], vec![3, 4]))), numbers.parse(input));
+    }
+
+    #[test]
+    fn paragraphs_of_bytes() {
+        let rows = paragraphs(one_or_more(line(one_or_more(byte(b'#')))));
+        assert_eq!(Ok((&b""[..], vec![vec![vec![b'#']], vec![vec![b'#', b'#']]])), rows.parse(&b"#\n\n##"[..]));
+    }
+}
@@ -0,0 +1,155 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use super::*;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize
+}
+
+type Cache<'a, Output, I> = HashMap<(usize, usize), ParseResult<'a, Output, I>>;
+
+// A parser which remembers its result at each input position, so alternatives
+// sharing a prefix only parse it once. Clones share the same cache, which is
+// how a prefix used in several alternatives gets the benefit.
+//
+// Positions are keyed by address and length. Every cached result borrows its
+// input for 'a, so no two distinct inputs seen by one cache can share an address.
+pub struct Memoized<'a, Output, I: ?Sized + 'a = str> {
+    parser: BoxedParser<'a, Output, I>,
+    cache: Rc<RefCell<Cache<'a, Output, I>>>,
+    stats: Rc<Cell<MemoStats>>
+}
+
+impl<'a, Output, I: ?Sized + 'a> Memoized<'a, Output, I> {
+    pub fn new<P>(parser: P) -> Self
+    where
+        P: Parser<'a, Output, I> + 'a,
+        Output: 'a
+    {
+        Memoized {
+            parser: parser.boxed(),
+            cache: Rc::new(RefCell::new(HashMap::new())),
+            stats: Rc::new(Cell::new(MemoStats::default()))
+        }
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        self.stats.get()
+    }
+
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear();
+        self.stats.set(MemoStats::default());
+    }
+
+    // Update the shared stats in place, so counts made by nested calls to the
+    // same parser aren't overwritten
+    fn count<F: FnOnce(&mut MemoStats)>(&self, update: F) {
+        let mut stats = self.stats.get();
+        update(&mut stats);
+        self.stats.set(stats);
+    }
+}
+
+impl<'a, Output, I: ?Sized + 'a> Clone for Memoized<'a, Output, I> {
+    fn clone(&self) -> Self {
+        Memoized {
+            parser: self.parser.clone(),
+            cache: self.cache.clone(),
+            stats: self.stats.clone()
+        }
+    }
+}
+
+impl<'a, Output, I> Parser<'a, Output, I> for Memoized<'a, Output, I>
+where
+    Output: Clone,
+    I: Input + ?Sized + 'a
+{
+    fn parse(&self, input: &'a I) -> ParseResult<'a, Output, I> {
+        let key = (input.as_bytes().as_ptr() as usize, input.len());
+
+        if let Some(result) = self.cache.borrow().get(&key) {
+            self.count(|stats| stats.hits += 1);
+            return result.clone();
+        }
+
+        // the cache isn't borrowed while parsing, as the parser may recurse into itself
+        let result = self.parser.parse(input);
+        self.count(|stats| stats.misses += 1);
+        self.cache.borrow_mut().insert(key, result.clone());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memoized_parser_runs_once_per_position() {
+        let runs = Rc::new(Cell::new(0));
+        let counter = runs.clone();
+        let counted = move |input| {
+            counter.set(counter.get() + 1);
+            integer.parse(input)
+        };
+
+        let number = Memoized::new(counted);
+        let parser = left(number.clone(), match_literal("+"))
+            .or(left(number.clone(), match_literal("*")))
+            .or(number.clone());
+
+        assert_eq!(Ok(("", 42)), parser.parse("42"));
+        assert_eq!(runs.get(), 1);
+        assert_eq!(number.stats(), MemoStats { hits: 2, misses: 1 });
+    }
+
+    #[test]
+    fn memoized_failures_are_cached() {
+        let word = match_literal("foo").memoized();
+        let parser = right(word.clone(), match_literal("1")).or(right(word.clone(), match_literal("2")));
+        assert_eq!(
+            Err(ParseError::expected("bar", "`foo`")),
+            parser.parse("bar")
+        );
+        assert_eq!(word.stats(), MemoStats { hits: 1, misses: 1 });
+    }
+
+    #[test]
+    fn recursive_parsers_count_nested_calls() {
+        // a run of `a`s, counted by recursing on the rest of the run
+        let rule: Rc<RefCell<Option<Memoized<'static, usize>>>> = Rc::new(RefCell::new(None));
+        let tail = rule.clone();
+        let run = Memoized::new(move |input: &'static str| match match_literal("a").parse(input) {
+            Ok((rest, _)) => {
+                let tail = tail.borrow().clone().unwrap();
+                tail.parse(rest).map(|(rest, n)| (rest, n + 1))
+            }
+            Err(_) => Ok((input, 0))
+        });
+        *rule.borrow_mut() = Some(run.clone());
+
+        assert_eq!(Ok(("", 3)), run.parse("aaa"));
+        assert_eq!(run.stats(), MemoStats { hits: 0, misses: 4 });
+        assert_eq!(Ok(("", 2)), run.parse(&"aaa"[1..]));
+        assert_eq!(run.stats(), MemoStats { hits: 1, misses: 4 });
+
+        // break the cycle between the rule and itself
+        rule.borrow_mut().take();
+    }
+
+    #[test]
+    fn clear_resets_cache_and_stats() {
+        let word = match_literal("foo").memoized();
+        let _ = word.parse("foo");
+        let _ = word.parse("foo");
+        word.clear();
+        assert_eq!(word.stats(), MemoStats::default());
+        let _ = word.parse("foo");
+        assert_eq!(word.stats(), MemoStats { hits: 0, misses: 1 });
+    }
+}
@@ -0,0 +1,211 @@
+use std::any::type_name;
+use std::marker::PhantomData;
+use super::*;
+
+// The primitive integer types, for parsers which can produce any of them
+pub trait PrimInt: Copy {
+    const ZERO: Self;
+
+    fn push_digit(self, radix: u32, digit: u32) -> Option<Self>;
+    fn push_negative_digit(self, radix: u32, digit: u32) -> Option<Self>;
+}
+
+macro_rules! impl_prim_int {
+    ($($t:ty)*) => {$(
+        impl PrimInt for $t {
+            const ZERO: Self = 0;
+
+            fn push_digit(self, radix: u32, digit: u32) -> Option<Self> {
+                self.checked_mul(radix as $t)?.checked_add(digit as $t)
+            }
+
+            fn push_negative_digit(self, radix: u32, digit: u32) -> Option<Self> {
+                self.checked_mul(radix as $t)?.checked_sub(digit as $t)
+            }
+        }
+    )*}
+}
+
+impl_prim_int! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize }
+
+// Negative numbers are accumulated downwards so that the minimum value of a
+// signed type can be parsed without overflowing on the way there
+fn digits<'a, T, I>(input: &'a I, radix: u32, negative: bool, what: &'static str) -> ParseResult<'a, T, I>
+where
+    T: PrimInt,
+    I: Input + ?Sized
+{
+    let bytes = input.as_bytes();
+    let len = bytes.iter().position(|b| !(*b as char).is_digit(radix)).unwrap_or(bytes.len());
+    if len == 0 {
+        return Err(ParseError::expected(input, what));
+    }
+
+    bytes[..len].iter()
+        .map(|b| (*b as char).to_digit(radix).unwrap())
+        .try_fold(T::ZERO, |n, digit|
+            if negative {
+                n.push_negative_digit(radix, digit)
+            } else {
+                n.push_digit(radix, digit)
+            }
+        )
+        .map(|n| (input.slice_from(len), n))
+        .ok_or_else(|| ParseError::expected(input, format!("{} in range of {}", what, type_name::<T>())))
+}
+
+// Parses an integer of type `T` from any kind of input, so `uint::<u8>()`
+// needs no annotation for whether it's reading a `str` or `[u8]`
+pub struct IntParser<T> {
+    radix: u32,
+    signed: bool,
+    sign_required: bool,
+    what: &'static str,
+    output: PhantomData<fn() -> T>
+}
+
+impl<'a, T, I> Parser<'a, T, I> for IntParser<T>
+where
+    T: PrimInt,
+    I: Input + ?Sized + 'a
+{
+    fn parse(&self, input: &'a I) -> ParseResult<'a, T, I> {
+        match input.as_bytes().first() {
+            Some(b'-') if self.signed => digits(input.slice_from(1), self.radix, true, self.what),
+            Some(b'+') if self.signed => digits(input.slice_from(1), self.radix, false, self.what),
+            _ if self.sign_required => Err(ParseError::expected(input, "`+` or `-`")),
+            _ => digits(input, self.radix, false, self.what)
+        }
+    }
+}
+
+impl<T> IntParser<T> {
+    // Only numbers written with a sign, as in "+3" and "-99"
+    pub fn sign_required(self) -> Self {
+        IntParser { signed: true, sign_required: true, ..self }
+    }
+}
+
+// Panics if `radix` isn't between 2 and 36, like `from_str_radix`
+pub fn uint_radix<T: PrimInt>(radix: u32) -> IntParser<T> {
+    assert!((2..=36).contains(&radix), "radix must be between 2 and 36, not {}", radix);
+    let what = match radix {
+        2 => "binary integer",
+        16 => "hexadecimal integer",
+        _ => "integer"
+    };
+    IntParser { radix, signed: false, sign_required: false, what, output: PhantomData }
+}
+
+pub fn uint<T: PrimInt>() -> IntParser<T> {
+    uint_radix(10)
+}
+
+pub fn int<T: PrimInt>() -> IntParser<T> {
+    IntParser { signed: true, ..uint() }
+}
+
+pub fn integer<I>(input: &I) -> ParseResult<'_, i64, I>
+where
+    I: Input + ?Sized
+{
+    uint().parse(input)
+}
+
+pub fn signed_integer<I>(input: &I) -> ParseResult<'_, i64, I>
+where
+    I: Input + ?Sized
+{
+    int().parse(input)
+}
+
+pub fn hex_integer<I>(input: &I) -> ParseResult<'_, i64, I>
+where
+    I: Input + ?Sized
+{
+    uint_radix(16).parse(input)
+}
+
+pub fn binary_integer<I>(input: &I) -> ParseResult<'_, i64, I>
+where
+    I: Input + ?Sized
+{
+    uint_radix(2).parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_reports_overflow() {
+        assert_eq!(Ok(("", i64::MAX)), integer.parse("9223372036854775807"));
+        assert_eq!(
+            Err(ParseError::expected("9223372036854775808", "integer in range of i64")),
+            integer.parse("9223372036854775808")
+        );
+    }
+
+    #[test]
+    fn signed_integer_parser() {
+        assert_eq!(Ok((" ", 3)), signed_integer.parse("+3 "));
+        assert_eq!(Ok(("", -99)), signed_integer.parse("-99"));
+        assert_eq!(Ok(("x", 42)), signed_integer.parse("42x"));
+        assert_eq!(Err(ParseError::expected("x", "integer")), signed_integer.parse("-x"));
+    }
+
+    #[test]
+    fn sign_required() {
+        let operand = int::<i64>().sign_required();
+        assert_eq!(Ok(("", 3)), operand.parse("+3"));
+        assert_eq!(Ok(("", -99)), operand.parse("-99"));
+        assert_eq!(Err(ParseError::expected("1", "`+` or `-`")), operand.parse("1"));
+    }
+
+    #[test]
+    fn hex_integer_parser() {
+        assert_eq!(Ok(("g", 0x123abc)), hex_integer.parse("123abcg"));
+        assert_eq!(Ok(("", 0xFF)), hex_integer.parse("FF"));
+        assert_eq!(Err(ParseError::expected("xyz", "hexadecimal integer")), hex_integer.parse("xyz"));
+    }
+
+    #[test]
+    fn binary_integer_parser() {
+        assert_eq!(Ok(("2", 0b1011)), binary_integer.parse("10112"));
+        assert_eq!(Err(ParseError::expected("2", "binary integer")), binary_integer.parse("2"));
+    }
+
+    #[test]
+    fn uint_sizes() {
+        assert_eq!(Ok(("", 255u8)), uint::<u8>().parse("255"));
+        assert_eq!(Err(ParseError::expected("256", "integer in range of u8")), uint::<u8>().parse("256"));
+        assert_eq!(Ok(("", u128::MAX)), uint::<u128>().parse("340282366920938463463374607431768211455"));
+        assert_eq!(Ok(("", 0xfffffffffu64)), uint_radix::<u64>(2).parse("111111111111111111111111111111111111"));
+    }
+
+    #[test]
+    #[should_panic(expected = "radix must be between 2 and 36")]
+    fn uint_radix_rejects_invalid_radix() {
+        uint_radix::<u32>(37);
+    }
+
+    #[test]
+    fn integers_from_bytes() {
+        let input: &[u8] = b"-42,17";
+        assert_eq!(Ok((&b",17"[..], -42)), signed_integer.parse(input));
+        assert_eq!(Ok((&b""[..], 17u8)), uint::<u8>().parse(&input[4..]));
+        assert_eq!(
+            Err(ParseError::expected(&b"-42,17"[..], "integer")),
+            integer.parse(input)
+        );
+    }
+
+    #[test]
+    fn int_sizes() {
+        assert_eq!(Ok(("", -128i8)), int::<i8>().parse("-128"));
+        assert_eq!(Err(ParseError::expected("129", "integer in range of i8")), int::<i8>().parse("-129"));
+        assert_eq!(Ok(("", 0u32)), int::<u32>().parse("-0"));
+        assert_eq!(Err(ParseError::expected("1", "integer in range of u32")), int::<u32>().parse("-1"));
+        assert_eq!(Ok(("", i128::MIN)), int::<i128>().parse("-170141183460469231731687303715884105728"));
+    }
+}
@@ -0,0 +1,66 @@
+use super::*;
+
+// Types which know their own syntax. With the `derive` feature this can be
+// derived from a format on each struct or enum variant:
+//
+//     #[derive(Parse)]
+//     enum Instruction {
+//         #[parse("mask = {0}")]
+//         Mask(#[parse(with = "mask")] String),
+//         #[parse("mem[{address}] = {value}")]
+//         Write { address: u64, value: u64 }
+//     }
+//
+// Each `{field}` is parsed with the field type's own `Parse` implementation,
+// unless the field has a `#[parse(with = "function")]` attribute naming a
+// parser function, like `mask` above: a `String` on its own only parses a
+// word of letters. A whole variant can also use `#[parse(with = "function")]`
+// when its syntax doesn't fit a format.
+pub trait Parse: Sized {
+    fn parser<'a>() -> BoxedParser<'a, Self>;
+}
+
+macro_rules! impl_parse_int {
+    ($f:ident: $($t:ty)*) => {$(
+        impl Parse for $t {
+            fn parser<'a>() -> BoxedParser<'a, Self> {
+                $f::<$t>().boxed()
+            }
+        }
+    )*}
+}
+
+impl_parse_int! { uint: u8 u16 u32 u64 u128 usize }
+impl_parse_int! { int: i8 i16 i32 i64 i128 isize }
+
+impl Parse for char {
+    fn parser<'a>() -> BoxedParser<'a, Self> {
+        any_char.boxed()
+    }
+}
+
+impl Parse for String {
+    fn parser<'a>() -> BoxedParser<'a, Self> {
+        word_ref.map(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_parse_according_to_sign() {
+        assert_eq!(Ok(("", -12)), i32::parser().parse("-12"));
+        assert_eq!(Err(ParseError::expected("-12", "integer")), u32::parser().parse("-12"));
+        assert_eq!(
+            Err(ParseError::expected("300", "integer in range of u8")),
+            u8::parser().parse("300")
+        );
+    }
+
+    #[test]
+    fn strings_parse_a_word() {
+        assert_eq!(Ok((" bags", "shiny".to_string())), String::parser().parse("shiny bags"));
+    }
+}
@@ -0,0 +1,290 @@
+use std::rc::Rc;
+use super::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right
+}
+
+type Unary<'a, E> = Rc<dyn Fn(E) -> E + 'a>;
+type Binary<'a, E> = Rc<dyn Fn(E, E) -> E + 'a>;
+
+struct PrefixOp<'a, E, I: ?Sized + 'a> {
+    token: BoxedParser<'a, (), I>,
+    right_bp: u32,
+    build: Unary<'a, E>
+}
+
+struct InfixOp<'a, E, I: ?Sized + 'a> {
+    token: BoxedParser<'a, (), I>,
+    left_bp: u32,
+    right_bp: u32,
+    build: Binary<'a, E>
+}
+
+struct PostfixOp<'a, E, I: ?Sized + 'a> {
+    token: BoxedParser<'a, (), I>,
+    left_bp: u32,
+    build: Unary<'a, E>
+}
+
+type Group<'a, I> = (BoxedParser<'a, (), I>, BoxedParser<'a, (), I>);
+
+// An expression parser driven by operator tables, using precedence climbing.
+// Operators with higher precedence bind more tightly. Each operator comes
+// with a function which combines its operands, so the result can be an AST
+// or can be evaluated directly.
+pub struct Pratt<'a, E, I: ?Sized + 'a = str> {
+    atom: BoxedParser<'a, E, I>,
+    groups: Vec<Group<'a, I>>,
+    prefix: Vec<PrefixOp<'a, E, I>>,
+    infix: Vec<InfixOp<'a, E, I>>,
+    postfix: Vec<PostfixOp<'a, E, I>>
+}
+
+fn merge_error<'a, I>(acc: Option<ParseError<'a, I>>, err: ParseError<'a, I>) -> ParseError<'a, I>
+where
+    I: Input + ?Sized
+{
+    match acc {
+        Some(acc) => acc.merge(err),
+        None => err
+    }
+}
+
+impl<'a, E, I> Pratt<'a, E, I>
+where
+    E: 'a,
+    I: Input + ?Sized + 'a
+{
+    pub fn new<P>(atom: P) -> Self
+    where
+        P: Parser<'a, E, I> + 'a
+    {
+        Pratt {
+            atom: atom.boxed(),
+            groups: vec![],
+            prefix: vec![],
+            infix: vec![],
+            postfix: vec![]
+        }
+    }
+
+    // Brackets around a complete sub-expression
+    pub fn group<PO, PC, RO, RC>(mut self, open: PO, close: PC) -> Self
+    where
+        PO: Parser<'a, RO, I> + 'a,
+        PC: Parser<'a, RC, I> + 'a,
+        RO: 'a,
+        RC: 'a
+    {
+        self.groups.push((open.means(()), close.means(())));
+        self
+    }
+
+    pub fn prefix<P, R, F>(mut self, token: P, precedence: u32, build: F) -> Self
+    where
+        P: Parser<'a, R, I> + 'a,
+        R: 'a,
+        F: Fn(E) -> E + 'a
+    {
+        self.prefix.push(PrefixOp {
+            token: token.means(()),
+            right_bp: precedence * 2,
+            build: Rc::new(build)
+        });
+        self
+    }
+
+    pub fn infix<P, R, F>(mut self, token: P, precedence: u32, assoc: Assoc, build: F) -> Self
+    where
+        P: Parser<'a, R, I> + 'a,
+        R: 'a,
+        F: Fn(E, E) -> E + 'a
+    {
+        let (left_bp, right_bp) = match assoc {
+            Assoc::Left => (precedence * 2, precedence * 2 + 1),
+            Assoc::Right => (precedence * 2 + 1, precedence * 2)
+        };
+        self.infix.push(InfixOp {
+            token: token.means(()),
+            left_bp,
+            right_bp,
+            build: Rc::new(build)
+        });
+        self
+    }
+
+    pub fn postfix<P, R, F>(mut self, token: P, precedence: u32, build: F) -> Self
+    where
+        P: Parser<'a, R, I> + 'a,
+        R: 'a,
+        F: Fn(E) -> E + 'a
+    {
+        self.postfix.push(PostfixOp {
+            token: token.means(()),
+            left_bp: precedence * 2,
+            build: Rc::new(build)
+        });
+        self
+    }
+
+    fn operand(&self, input: &'a I) -> ParseResult<'a, E, I> {
+        let mut error = None;
+
+        for op in self.prefix.iter() {
+            match op.token.parse(input) {
+                Ok((next_input, _)) => {
+                    let (next_input, operand) = self.expression(next_input, op.right_bp)?;
+                    return Ok((next_input, (op.build)(operand)));
+                }
+                Err(err) => error = Some(merge_error(error, err))
+            }
+        }
+
+        for (open, close) in self.groups.iter() {
+            match open.parse(input) {
+                Ok((next_input, _)) => {
+                    let (next_input, inner) = self.expression(next_input, 0)?;
+                    let (next_input, _) = close.parse(next_input)?;
+                    return Ok((next_input, inner));
+                }
+                Err(err) => error = Some(merge_error(error, err))
+            }
+        }
+
+        self.atom.parse(input).map_err(|err| merge_error(error, err))
+    }
+
+    // Parse operators binding at least as tightly as `min_bp` onto the operand
+    fn expression(&self, input: &'a I, min_bp: u32) -> ParseResult<'a, E, I> {
+        let (mut input, mut lhs) = self.operand(input)?;
+
+        'operators: loop {
+            for op in self.postfix.iter().filter(|op| op.left_bp >= min_bp) {
+                if let Ok((next_input, _)) = op.token.parse(input) {
+                    lhs = (op.build)(lhs);
+                    input = next_input;
+                    continue 'operators;
+                }
+            }
+
+            for op in self.infix.iter().filter(|op| op.left_bp >= min_bp) {
+                if let Ok((next_input, _)) = op.token.parse(input) {
+                    let (next_input, rhs) = self.expression(next_input, op.right_bp)?;
+                    lhs = (op.build)(lhs, rhs);
+                    input = next_input;
+                    continue 'operators;
+                }
+            }
+
+            return Ok((input, lhs));
+        }
+    }
+}
+
+impl<'a, E, I> Parser<'a, E, I> for Pratt<'a, E, I>
+where
+    E: 'a,
+    I: Input + ?Sized + 'a
+{
+    fn parse(&self, input: &'a I) -> ParseResult<'a, E, I> {
+        self.expression(input, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Expr {
+        Num(i64),
+        Neg(Box<Expr>),
+        Fact(Box<Expr>),
+        Bin(char, Box<Expr>, Box<Expr>)
+    }
+
+    fn bin(op: char) -> impl Fn(Expr, Expr) -> Expr {
+        move |a, b| Expr::Bin(op, Box::new(a), Box::new(b))
+    }
+
+    fn symbol<'a>(s: &'static str) -> impl Parser<'a, ()> {
+        whitespace_wrap(match_literal(s))
+    }
+
+    fn ast<'a>() -> Pratt<'a, Expr> {
+        Pratt::new(whitespace_wrap(integer).map(Expr::Num))
+            .group(symbol("("), symbol(")"))
+            .prefix(symbol("-"), 3, |e| Expr::Neg(Box::new(e)))
+            .postfix(symbol("!"), 5, |e| Expr::Fact(Box::new(e)))
+            .infix(symbol("+"), 1, Assoc::Left, bin('+'))
+            .infix(symbol("-"), 1, Assoc::Left, bin('-'))
+            .infix(symbol("*"), 2, Assoc::Left, bin('*'))
+            .infix(symbol("^"), 4, Assoc::Right, bin('^'))
+    }
+
+    fn show(e: &Expr) -> String {
+        match e {
+            Expr::Num(n) => n.to_string(),
+            Expr::Neg(e) => format!("(-{})", show(e)),
+            Expr::Fact(e) => format!("({}!)", show(e)),
+            Expr::Bin(op, a, b) => format!("({} {} {})", show(a), op, show(b))
+        }
+    }
+
+    fn parse_shown(input: &str) -> String {
+        let (rest, e) = ast().parse(input).unwrap();
+        assert_eq!(rest, "");
+        show(&e)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(parse_shown("1 + 2 * 3"), "(1 + (2 * 3))");
+        assert_eq!(parse_shown("1 * 2 + 3"), "((1 * 2) + 3)");
+    }
+
+    #[test]
+    fn associativity() {
+        assert_eq!(parse_shown("1 - 2 - 3"), "((1 - 2) - 3)");
+        assert_eq!(parse_shown("2 ^ 3 ^ 2"), "(2 ^ (3 ^ 2))");
+    }
+
+    #[test]
+    fn prefix_and_postfix() {
+        assert_eq!(parse_shown("-2 ^ 2"), "(-(2 ^ 2))");
+        assert_eq!(parse_shown("-2 + 2"), "((-2) + 2)");
+        assert_eq!(parse_shown("-3!"), "(-(3!))");
+        assert_eq!(parse_shown("2 * 3!"), "(2 * (3!))");
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(parse_shown("(1 + 2) * 3"), "((1 + 2) * 3)");
+        assert_eq!(parse_shown("-(1 - (2 - 3))"), "(-(1 - (2 - 3)))");
+    }
+
+    #[test]
+    fn evaluating_directly() {
+        let expr = Pratt::new(whitespace_wrap(integer))
+            .group(symbol("("), symbol(")"))
+            .infix(symbol("+"), 1, Assoc::Left, |a, b| a + b)
+            .infix(symbol("*"), 2, Assoc::Left, |a, b| a * b);
+        assert_eq!(Ok(("", 21)), expr.parse("3 * (2 + 5)"));
+    }
+
+    #[test]
+    fn missing_operand() {
+        let input = "1 + * 2";
+        let error = ast().parse(input).unwrap_err();
+        assert_eq!(error.remaining, "* 2");
+        assert_eq!(error.expected, vec!["`-`", "`(`", "integer"]);
+    }
+
+    #[test]
+    fn unclosed_group() {
+        assert_eq!(Err(ParseError::expected("", "`)`")), ast().parse("(1 + 2"));
+    }
+}
@@ -0,0 +1,138 @@
+use super::*;
+
+// The inverse of `Parse`: renders a value in the syntax its parser reads, so
+// that parse → modify → print reproduces the unmodified parts of an input
+// exactly. With the `derive` feature this can be derived from the same
+// `#[parse(...)]` formats as `Parse`; variants which parse `with` a function
+// also need `unparse = "function"` taking the whole value.
+pub trait Unparse {
+    fn unparse(&self, out: &mut String);
+
+    fn unparsed(&self) -> String {
+        let mut out = String::new();
+        self.unparse(&mut out);
+        out
+    }
+}
+
+macro_rules! impl_unparse_display {
+    ($($t:ty)*) => {$(
+        impl Unparse for $t {
+            fn unparse(&self, out: &mut String) {
+                out.push_str(&self.to_string());
+            }
+        }
+    )*}
+}
+
+impl_unparse_display! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize char }
+
+impl Unparse for str {
+    fn unparse(&self, out: &mut String) {
+        out.push_str(self);
+    }
+}
+
+impl Unparse for String {
+    fn unparse(&self, out: &mut String) {
+        out.push_str(self);
+    }
+}
+
+impl<T: Unparse + ?Sized> Unparse for &T {
+    fn unparse(&self, out: &mut String) {
+        (*self).unparse(out)
+    }
+}
+
+// Printers are to `Unparse` what parser functions are to `Parse`: they render
+// values whose syntax depends on the context, such as collections.
+pub trait Printer<T: ?Sized> {
+    fn print(&self, value: &T, out: &mut String);
+
+    fn printed(&self, value: &T) -> String {
+        let mut out = String::new();
+        self.print(value, &mut out);
+        out
+    }
+}
+
+impl<T: ?Sized, F> Printer<T> for F
+where
+    F: Fn(&T, &mut String)
+{
+    fn print(&self, value: &T, out: &mut String) {
+        self(value, out)
+    }
+}
+
+pub fn unparser<T: Unparse + ?Sized>() -> impl Printer<T> {
+    |value: &T, out: &mut String| value.unparse(out)
+}
+
+// The counterpart of `sep_by`
+pub fn print_sep_by<T, P>(item: P, sep: &'static str) -> impl Printer<[T]>
+where
+    P: Printer<T>
+{
+    move |items: &[T], out: &mut String| {
+        for (index, value) in items.iter().enumerate() {
+            if index > 0 {
+                out.push_str(sep);
+            }
+            item.print(value, out);
+        }
+    }
+}
+
+// One item per line, each ending in a newline, as read by `recover_lines`
+// or `one_or_more(whitespace_wrap(..))`
+pub fn print_lines<T, P>(item: P) -> impl Printer<[T]>
+where
+    P: Printer<T>
+{
+    move |items: &[T], out: &mut String| {
+        for value in items.iter() {
+            item.print(value, out);
+            out.push('\n');
+        }
+    }
+}
+
+// Whether a value survives being printed and parsed back, for round-trip tests
+pub fn round_trips<T>(value: &T) -> bool
+where
+    T: Parse + Unparse + PartialEq
+{
+    let text = value.unparsed();
+    let parser = T::parser();
+    let result = parser.parse(&text);
+    matches!(result, Ok(("", ref parsed)) if parsed == value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_unparse_as_displayed() {
+        assert_eq!((-12i64).unparsed(), "-12");
+        assert_eq!('x'.unparsed(), "x");
+        assert_eq!("shiny".unparsed(), "shiny");
+    }
+
+    #[test]
+    fn printer_combinators() {
+        let numbers = [1u8, 2, 3];
+        assert_eq!(print_sep_by(unparser(), ", ").printed(&numbers[..]), "1, 2, 3");
+        assert_eq!(print_lines(unparser()).printed(&numbers[..]), "1\n2\n3\n");
+        assert_eq!(print_lines(unparser::<u8>()).printed(&[][..]), "");
+    }
+
+    #[test]
+    fn primitives_round_trip() {
+        assert!(round_trips(&i64::MIN));
+        assert!(round_trips(&u8::MAX));
+        assert!(round_trips(&"word".to_string()));
+    }
+}
@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+use super::*;
+
+// String literals between a pair of quote characters. A backslash escapes any
+// quote character, another backslash, `\n`, `\r` or `\t`, and `\u{1F600}`
+// gives a unicode scalar value by its hexadecimal code.
+
+pub fn quoted_string<'a>() -> impl Parser<'a, String> {
+    quoted('"')
+}
+
+// A string between `quote` characters, such as '\'' or '`'
+pub fn quoted<'a>(quote: char) -> impl Parser<'a, String> {
+    quoted_str(quote).map(Cow::into_owned)
+}
+
+// Like `quoted`, but borrowing the contents from the input unless they
+// contain escapes, which can only be resolved into a new string
+pub fn quoted_str<'a>(quote: char) -> impl Parser<'a, Cow<'a, str>> {
+    move |input: &'a str| {
+        let contents = input.strip_prefix(quote)
+            .ok_or_else(|| ParseError::expected(input, format!("`{}`", quote)))?;
+
+        let mut unescaped: Option<String> = None;
+        let mut rest = contents;
+
+        loop {
+            let mut chars = rest.chars();
+            match chars.next() {
+                Some(c) if c == quote => {
+                    let value = match unescaped {
+                        Some(s) => Cow::Owned(s),
+                        None => Cow::Borrowed(&contents[..contents.len() - rest.len()])
+                    };
+                    return Ok((chars.as_str(), value));
+                }
+
+                Some('\\') => {
+                    let (next_rest, c) = escape(rest)?;
+                    unescaped.get_or_insert_with(|| contents[..contents.len() - rest.len()].to_string())
+                        .push(c);
+                    rest = next_rest;
+                }
+
+                Some(c) => {
+                    if let Some(s) = unescaped.as_mut() {
+                        s.push(c);
+                    }
+                    rest = chars.as_str();
+                }
+
+                None => return Err(ParseError::expected(rest, format!("closing `{}`", quote)))
+            }
+        }
+    }
+}
+
+// An escape sequence, starting at its backslash
+fn escape(input: &str) -> ParseResult<'_, char> {
+    let mut chars = input[1..].chars();
+    let c = match chars.next() {
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some(c @ ('\\' | '"' | '\'' | '`')) => c,
+        Some('u') => return unicode_escape(chars.as_str())
+            .map_err(|_| ParseError::expected(input, "unicode escape like `\\u{1F600}`")),
+        _ => return Err(ParseError::expected(input, "escape sequence"))
+    };
+    Ok((chars.as_str(), c))
+}
+
+fn unicode_escape(input: &str) -> ParseResult<'_, char> {
+    let (rest, code) = uint_radix::<u32>(16)
+        .between(match_literal("{"), match_literal("}"))
+        .parse(input)?;
+    match char::from_u32(code) {
+        Some(c) => Ok((rest, c)),
+        None => Err(ParseError::new(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            Ok((" rest", "say \"hi\"\\\n\t😀!".to_string())),
+            quoted_string().parse(r#""say \"hi\"\\\n\t\u{1F600}\u{21}" rest"#)
+        );
+    }
+
+    #[test]
+    fn other_quote_characters() {
+        assert_eq!(Ok(("", "it's \"fine\"".to_string())), quoted('`').parse("`it's \"fine\"`"));
+        assert_eq!(Ok(("", "don't".to_string())), quoted('\'').parse(r"'don\'t'"));
+        assert_eq!(Err(ParseError::expected("\"x\"", "`'`")), quoted('\'').parse("\"x\""));
+    }
+
+    #[test]
+    fn borrowed_unless_escaped() {
+        assert!(matches!(quoted_str('"').parse("\"plain\""), Ok(("", Cow::Borrowed("plain")))));
+        match quoted_str('"').parse(r#""a\nb""#) {
+            Ok(("", Cow::Owned(s))) => assert_eq!(s, "a\nb"),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+
+    #[test]
+    fn malformed_strings() {
+        assert_eq!(Err(ParseError::expected("", "closing `\"`")), quoted_string().parse("\"open"));
+        assert_eq!(Err(ParseError::expected("\\q\"", "escape sequence")), quoted_string().parse("\"a\\q\""));
+        assert_eq!(
+            Err(ParseError::expected("\\u{D800}\"", "unicode escape like `\\u{1F600}`")),
+            quoted_string().parse("\"\\u{D800}\"")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use super::*;
+
+// Repetition with bounds on the number of items, for fixed-width fields like
+// "exactly 9 digits". When too few items match, the error is the one from the
+// item which failed, so it points at the place the input falls short.
+
+// `parser` between `min` and `max` times, or any number of times from `min`
+// if there's no maximum. Stops as soon as `max` items have matched.
+fn repeat<'a, P, A, I>(parser: P, min: usize, max: Option<usize>) -> impl Parser<'a, Vec<A>, I>
+where
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    move |mut input| {
+        let mut result = Vec::new();
+
+        while Some(result.len()) != max {
+            match parser.parse(input) {
+                Ok((next_input, item)) => {
+                    input = next_input;
+                    result.push(item);
+                }
+                Err(err) if result.len() < min => return Err(err),
+                Err(_) => break
+            }
+        }
+
+        Ok((input, result))
+    }
+}
+
+// Exactly `n` items
+pub fn count<'a, P, A, I>(parser: P, n: usize) -> impl Parser<'a, Vec<A>, I>
+where
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    repeat(parser, n, Some(n))
+}
+
+pub fn at_least<'a, P, A, I>(parser: P, min: usize) -> impl Parser<'a, Vec<A>, I>
+where
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    repeat(parser, min, None)
+}
+
+// From `min` to `max` items inclusive. Panics if `min` is more than `max`,
+// as no number of items would do.
+pub fn between_counts<'a, P, A, I>(parser: P, min: usize, max: usize) -> impl Parser<'a, Vec<A>, I>
+where
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    assert!(min <= max, "between_counts needs min <= max, not {} > {}", min, max);
+    repeat(parser, min, Some(max))
+}
+
+// Exactly `n` items with `sep` between each pair, such as the three commas
+// of "1,2,3,4" when `n` is 4
+pub fn separated_exact<'a, P, A, PS, S, I>(parser: P, sep: PS, n: usize) -> impl Parser<'a, Vec<A>, I>
+where
+    I: ?Sized + 'a,
+    P: Parser<'a, A, I>,
+    PS: Parser<'a, S, I>
+{
+    move |mut input| {
+        let mut result = Vec::with_capacity(n);
+
+        for i in 0..n {
+            if i > 0 {
+                input = sep.parse(input)?.0;
+            }
+            let (next_input, item) = parser.parse(input)?;
+            input = next_input;
+            result.push(item);
+        }
+
+        Ok((input, result))
+    }
+}
+
+// Items up to and including `end`, which is tried before each item. Running
+// out of input before `end` is an error from `parser`.
+pub fn many_till<'a, P, A, PE, E, I>(parser: P, end: PE) -> impl Parser<'a, Vec<A>, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>,
+    PE: Parser<'a, E, I>
+{
+    move |mut input: &'a I| {
+        let mut result = Vec::new();
+
+        loop {
+            match end.parse(input) {
+                Ok((next_input, _)) => return Ok((next_input, result)),
+                Err(end_err) => match parser.parse(input) {
+                    Ok((next_input, item)) => {
+                        input = next_input;
+                        result.push(item);
+                    }
+                    Err(err) => return Err(end_err.merge(err))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_counts() {
+        assert_eq!(Ok(("4", vec!['1', '2', '3'])), count(digit, 3).parse("1234"));
+        assert_eq!(Err(ParseError::expected("x", "digit")), count(digit, 3).parse("12x"));
+        assert_eq!(Ok(("abc", vec![])), count(digit, 0).parse("abc"));
+    }
+
+    #[test]
+    fn bounded_counts() {
+        assert_eq!(Ok(("", vec!['1', '2'])), at_least(digit, 2).parse("12"));
+        assert_eq!(Ok(("x", vec!['1', '2', '3'])), at_least(digit, 2).parse("123x"));
+        assert_eq!(Err(ParseError::expected("x", "digit")), at_least(digit, 2).parse("1x"));
+        assert_eq!(Ok(("34", vec!['1', '2'])), between_counts(digit, 1, 2).parse("1234"));
+        assert_eq!(Ok(("x", vec!['1'])), between_counts(digit, 1, 2).parse("1x"));
+        assert_eq!(Err(ParseError::expected("x", "digit")), between_counts(digit, 1, 2).parse("x"));
+    }
+
+    #[test]
+    #[should_panic(expected = "between_counts needs min <= max")]
+    fn between_counts_rejects_empty_range() {
+        let _ = between_counts(digit, 5, 2);
+    }
+
+    #[test]
+    fn exact_separated_counts() {
+        let triple = separated_exact(integer, match_literal(","), 3);
+        assert_eq!(Ok((",4", vec![1, 2, 3])), triple.parse("1,2,3,4"));
+        assert_eq!(Err(ParseError::expected("", "`,`")), triple.parse("1,2"));
+        assert_eq!(Err(ParseError::expected("x", "integer")), triple.parse("1,2,x"));
+    }
+
+    #[test]
+    fn many_till_end() {
+        let comment = right(match_literal("/*"), many_till(any_char, match_literal("*/")));
+        assert_eq!(Ok((" x", vec!['a', '*', 'b'])), comment.parse("/*a*b*/ x"));
+        assert_eq!(Ok(("", vec![])), comment.parse("/**/"));
+
+        let err = comment.parse("/*ab").unwrap_err();
+        assert_eq!(err.remaining, "");
+        assert!(err.expected.contains(&"`*/`".to_string()));
+    }
+
+    #[test]
+    fn fixed_width_bytes() {
+        let rgb = right(byte(b'#'), count(any_byte, 6));
+        assert_eq!(Ok((&b""[..], b"a1b2c3".to_vec())), rgb.parse(&b"#a1b2c3"[..]));
+        assert!(rgb.parse(&b"#a1b"[..]).is_err());
+    }
+}
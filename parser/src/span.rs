@@ -0,0 +1,79 @@
+use std::ops::Deref;
+use super::*;
+use super::error::line_and_column;
+
+// A parsed value and where it came from, for pointing back at the input when
+// a record parses but turns out to be unusable. Like a `ParseError`, it only
+// knows how much input was left when it started, so `offset` and `locate`
+// need the source text the whole parse began with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub value: T,
+    // bytes of input from the start of the value to the end of the source
+    pub remaining: usize,
+    // bytes the value was parsed from
+    pub len: usize
+}
+
+impl<T> Spanned<T> {
+    pub fn offset<I: Input + ?Sized>(&self, source: &I) -> usize {
+        source.len() - self.remaining
+    }
+
+    // The line and column of the start of the value, counting from 1 as in
+    // `Error`
+    pub fn locate<I: Input + ?Sized>(&self, source: &I) -> (usize, usize) {
+        line_and_column(source, self.offset(source))
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Unparse> Unparse for Spanned<T> {
+    fn unparse(&self, out: &mut String) {
+        self.value.unparse(out)
+    }
+}
+
+pub(crate) fn spanned<'a, P, A, I>(parser: P) -> impl Parser<'a, Spanned<A>, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    move |input: &'a I| {
+        let (rest, value) = parser.parse(input)?;
+        Ok((rest, Spanned { value, remaining: input.len(), len: input.len() - rest.len() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_locate_records() {
+        let source = "1\n  22\nx";
+        let (_, recovered) = recover_lines(integer.spanned()).parse(source).unwrap();
+        assert_eq!(recovered.records, vec![
+            Spanned { value: 1, remaining: 8, len: 1 },
+            Spanned { value: 22, remaining: 4, len: 2 }
+        ]);
+        assert_eq!(recovered.records[1].offset(source), 4);
+        assert_eq!(recovered.records[1].locate(source), (2, 3));
+        assert_eq!(*recovered.records[1] + 1, 23);
+    }
+
+    #[test]
+    fn spans_in_bytes() {
+        let source = &b"ab\n\n#"[..];
+        let (_, hash) = right(match_literal("ab\n\n"), byte(b'#').spanned()).parse(source).unwrap();
+        assert_eq!((hash.offset(source), hash.len), (4, 1));
+        assert_eq!(hash.locate(source), (3, 1));
+    }
+}
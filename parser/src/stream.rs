@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io::BufRead;
+use std::marker::PhantomData;
+use super::*;
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(std::io::Error),
+    Parse(Error)
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "read error: {}", err),
+            StreamError::Parse(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+// Parses one record per line from a buffered reader, so only the current line
+// needs to be held in memory. Blank lines are skipped; anything other than
+// whitespace left on a line after its record is an error.
+pub struct Records<R, P, A> {
+    reader: R,
+    parser: P,
+    line: String,
+    line_number: usize,
+    offset: usize,
+    record: PhantomData<fn() -> A>
+}
+
+// The parser has to work for any lifetime because each line is borrowed only
+// until the next is read. Parser functions like `integer` satisfy this, as
+// do functions which build a grammar and apply it to their input.
+pub fn records<R, P, A>(reader: R, parser: P) -> Records<R, P, A>
+where
+    R: BufRead,
+    P: for<'b> Parser<'b, A>
+{
+    Records {
+        reader,
+        parser,
+        line: String::new(),
+        line_number: 0,
+        offset: 0,
+        record: PhantomData
+    }
+}
+
+impl<R, P, A> Records<R, P, A>
+where
+    P: for<'b> Parser<'b, A>
+{
+    fn parse_line(&self, offset: usize) -> Result<A, Error> {
+        let line = self.line.trim_end_matches(['\n', '\r']);
+        let err = match self.parser.parse(line) {
+            Ok((rest, record)) if rest.trim().is_empty() => return Ok(record),
+            Ok((rest, _)) => ParseError::expected(rest, "end of line"),
+            Err(err) => err
+        };
+
+        let mut error = err.locate(line);
+        error.line = self.line_number;
+        error.offset += offset;
+        Err(error)
+    }
+}
+
+impl<R, P, A> Iterator for Records<R, P, A>
+where
+    R: BufRead,
+    P: for<'b> Parser<'b, A>
+{
+    type Item = Result<A, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Err(err) => return Some(Err(StreamError::Io(err))),
+                Ok(0) => return None,
+                Ok(len) => {
+                    let offset = self.offset;
+                    self.offset += len;
+                    self.line_number += 1;
+                    if !self.line.trim().is_empty() {
+                        return Some(self.parse_line(offset).map_err(StreamError::Parse));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_from_reader() {
+        let input = "1721\n979\n\n366\n";
+        let numbers: Vec<i64> = records(input.as_bytes(), integer)
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(numbers, vec![1721, 979, 366]);
+    }
+
+    #[test]
+    fn records_with_grammar_function() {
+        fn pair_of_ints(input: &str) -> ParseResult<'_, (i64, i64)> {
+            tuple2(integer, right(match_literal(","), integer)).parse(input)
+        }
+
+        let input = "1,2\r\n3,4  \n";
+        let pairs: Vec<(i64, i64)> = records(input.as_bytes(), pair_of_ints)
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(pairs, vec![(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn record_errors_are_positioned_in_stream() {
+        let input = "1\n2\n\n3x\n4\n";
+        let results: Vec<Result<i64, StreamError>> = records(input.as_bytes(), integer).collect();
+        assert_eq!(results.len(), 4);
+        match &results[2] {
+            Err(StreamError::Parse(err)) => {
+                assert_eq!(err.line, 4);
+                assert_eq!(err.column, 2);
+                assert_eq!(err.offset, 6);
+                assert_eq!(err.expected, vec!["end of line"]);
+            }
+            other => panic!("unexpected {:?}", other)
+        }
+        assert_eq!(results[3].as_ref().ok(), Some(&4));
+    }
+}
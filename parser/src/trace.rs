@@ -0,0 +1,297 @@
+use super::*;
+#[cfg(feature = "trace")]
+use std::cell::RefCell;
+#[cfg(feature = "trace")]
+use std::fmt;
+#[cfg(feature = "trace")]
+use std::io::{self, BufRead, Write};
+#[cfg(feature = "trace")]
+use std::fs::File;
+#[cfg(feature = "trace")]
+use std::path::Path;
+
+// With the `trace` feature, every named parser (given a name by `traced`,
+// `label` or `context`) logs when it's tried, whether it succeeded, and how
+// much input it consumed, at the log crate's trace level. Lines are indented
+// by how deeply the parsers are nested, so a failing grammar reads like a
+// call tree:
+//
+//     RUST_LOG=parser=trace cargo run --features parser/trace
+//
+// The same events can be recorded to a file with `record_trace` or
+// `record_trace_to_file` and logged again later by `replay_trace`. Without
+// the feature, naming a parser costs nothing.
+
+#[cfg(not(feature = "trace"))]
+pub(crate) fn traced<'a, P, A, I>(parser: P, _name: String) -> impl Parser<'a, A, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    parser
+}
+
+#[cfg(feature = "trace")]
+pub(crate) fn traced<'a, P, A, I>(parser: P, name: String) -> impl Parser<'a, A, I>
+where
+    I: Input + ?Sized + 'a,
+    P: Parser<'a, A, I>
+{
+    move |input: &'a I| {
+        let (depth, offset) = TRACER.with(|tracer| tracer.borrow_mut().enter(input.len()));
+        emit(TraceEvent::Enter { depth, offset, name: name.clone() });
+
+        // the tracer isn't borrowed while parsing, as named parsers nest
+        let result = parser.parse(input);
+        let event = match &result {
+            Ok((rest, _)) =>
+                TraceEvent::Success { depth, offset, consumed: input.len() - rest.len(), name: name.clone() },
+            Err(err) =>
+                TraceEvent::Failure { depth, offset, consumed: input.len().saturating_sub(err.remaining.len()), name: name.clone() }
+        };
+        emit(event);
+
+        TRACER.with(|tracer| tracer.borrow_mut().depth -= 1);
+        result
+    }
+}
+
+// One step in a trace. `offset` is where the parser started, in bytes from
+// the start of the input given to the outermost named parser, and `consumed`
+// is how far it got before succeeding or failing.
+#[cfg(feature = "trace")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    Enter { depth: usize, offset: usize, name: String },
+    Success { depth: usize, offset: usize, consumed: usize, name: String },
+    Failure { depth: usize, offset: usize, consumed: usize, name: String }
+}
+
+#[cfg(feature = "trace")]
+impl TraceEvent {
+    fn log(&self) {
+        match self {
+            TraceEvent::Enter { depth, offset, name } =>
+                log::trace!("{:indent$}{} at {}", "", name, offset, indent = depth * 2),
+            TraceEvent::Success { depth, consumed, name, .. } =>
+                log::trace!("{:indent$}{} matched {} bytes", "", name, consumed, indent = depth * 2),
+            TraceEvent::Failure { depth, consumed, name, .. } =>
+                log::trace!("{:indent$}{} failed after {} bytes", "", name, consumed, indent = depth * 2)
+        }
+    }
+}
+
+// The line format used in trace files, such as `ok 1 12 3 "rule name"`
+#[cfg(feature = "trace")]
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceEvent::Enter { depth, offset, name } =>
+                write!(f, "enter {} {} \"{}\"", depth, offset, name.escape_default()),
+            TraceEvent::Success { depth, offset, consumed, name } =>
+                write!(f, "ok {} {} {} \"{}\"", depth, offset, consumed, name.escape_default()),
+            TraceEvent::Failure { depth, offset, consumed, name } =>
+                write!(f, "fail {} {} {} \"{}\"", depth, offset, consumed, name.escape_default())
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+#[derive(Default)]
+struct Tracer {
+    depth: usize,
+    input_len: usize,
+    file: Option<Box<dyn Write>>,
+    error: Option<io::Error>
+}
+
+#[cfg(feature = "trace")]
+impl Tracer {
+    // The depth and offset of a parser starting on input of `len` bytes
+    fn enter(&mut self, len: usize) -> (usize, usize) {
+        if self.depth == 0 {
+            self.input_len = len;
+        }
+        self.depth += 1;
+        (self.depth - 1, self.input_len.saturating_sub(len))
+    }
+
+    fn record(&mut self, event: &TraceEvent) {
+        if self.error.is_none() {
+            if let Some(file) = self.file.as_mut() {
+                self.error = writeln!(file, "{}", event).err();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+thread_local! {
+    static TRACER: RefCell<Tracer> = RefCell::new(Tracer::default());
+}
+
+#[cfg(feature = "trace")]
+fn emit(event: TraceEvent) {
+    event.log();
+    TRACER.with(|tracer| tracer.borrow_mut().record(&event));
+}
+
+// Write the events of every named parser run on this thread to `file`, as
+// well as logging them, until `finish_trace`
+#[cfg(feature = "trace")]
+pub fn record_trace<W: Write + 'static>(file: W) {
+    TRACER.with(|tracer| {
+        let mut tracer = tracer.borrow_mut();
+        tracer.file = Some(Box::new(file));
+        tracer.error = None;
+    });
+}
+
+// Record to a newly created file at `path`. The file isn't buffered, so the
+// trace is complete up to the failing parser even if parsing then panics.
+#[cfg(feature = "trace")]
+pub fn record_trace_to_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    record_trace(File::create(path)?);
+    Ok(())
+}
+
+// Stop recording, reporting the first error writing the trace if there was one
+#[cfg(feature = "trace")]
+pub fn finish_trace() -> io::Result<()> {
+    TRACER.with(|tracer| {
+        let mut tracer = tracer.borrow_mut();
+        let file = tracer.file.take();
+        match tracer.error.take() {
+            Some(err) => Err(err),
+            None => file.map_or(Ok(()), |mut file| file.flush())
+        }
+    })
+}
+
+#[cfg(feature = "trace")]
+fn event(input: &str) -> ParseResult<'_, TraceEvent> {
+    let number = || whitespace_wrap(uint::<usize>());
+    let name = || whitespace_wrap(quoted_string());
+    let enter = right(
+        match_literal("enter"),
+        tuple3(number(), number(), name())
+    ).map(|(depth, offset, name)| TraceEvent::Enter { depth, offset, name });
+    let success = right(
+        match_literal("ok"),
+        tuple3(number(), tuple2(number(), number()), name())
+    ).map(|(depth, (offset, consumed), name)| TraceEvent::Success { depth, offset, consumed, name });
+    let failure = right(
+        match_literal("fail"),
+        tuple3(number(), tuple2(number(), number()), name())
+    ).map(|(depth, (offset, consumed), name)| TraceEvent::Failure { depth, offset, consumed, name });
+
+    // not labelled, as replaying a trace would then trace its own parsing
+    one_of3(enter, success, failure).parse(input)
+}
+
+// Log the events in a trace file again, as if the parsers had just run
+#[cfg(feature = "trace")]
+pub fn replay_trace<R: BufRead>(reader: R) -> Result<Vec<TraceEvent>, StreamError> {
+    records(reader, event)
+        .map(|event| event.inspect(TraceEvent::log))
+        .collect()
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    thread_local! {
+        static LOGGED: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    }
+
+    struct TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            LOGGED.with(|logged| logged.borrow_mut().push(record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: TestLogger = TestLogger;
+
+    fn logged<F: FnOnce()>(f: F) -> Vec<String> {
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+        LOGGED.with(|logged| logged.borrow_mut().clear());
+        f();
+        LOGGED.with(|logged| logged.borrow_mut().split_off(0))
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedFile(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedFile {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn pair_of_numbers<'a>() -> impl Parser<'a, (i64, i64)> {
+        tuple2(integer.traced("first"), right(match_literal(","), integer).label("second"))
+            .traced("pair")
+    }
+
+    #[test]
+    fn named_parsers_are_logged_by_depth() {
+        let lines = logged(|| {
+            assert!(pair_of_numbers().parse("12,x").is_err());
+        });
+        assert_eq!(lines, vec![
+            "pair at 0",
+            "  first at 0",
+            "  first matched 2 bytes",
+            "  second at 2",
+            "  second failed after 1 bytes",
+            "pair failed after 3 bytes"
+        ]);
+    }
+
+    #[test]
+    fn traces_replay_the_same_events() {
+        let file = SharedFile::default();
+        record_trace(file.clone());
+        let live = logged(|| {
+            assert_eq!(pair_of_numbers().parse("12,34"), Ok(("", (12, 34))));
+            assert!(integer.traced("say \"hi\"\n").parse("x").is_err());
+        });
+        finish_trace().unwrap();
+
+        let text = String::from_utf8(file.0.borrow().clone()).unwrap();
+        assert!(text.starts_with("enter 0 0 \"pair\"\nenter 1 0 \"first\"\nok 1 0 2 \"first\"\n"));
+
+        let mut events = vec![];
+        let replayed = logged(|| events = replay_trace(text.as_bytes()).unwrap());
+        assert_eq!(replayed, live);
+        assert_eq!(events.last(), Some(&TraceEvent::Failure {
+            depth: 0,
+            offset: 0,
+            consumed: 0,
+            name: "say \"hi\"\n".to_string()
+        }));
+    }
+
+    #[test]
+    fn malformed_traces_are_located() {
+        match replay_trace("enter 0 0 \"a\"\nexit 0 0 \"a\"\n".as_bytes()) {
+            Err(StreamError::Parse(err)) => assert_eq!((err.line, err.column), (2, 1)),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+}